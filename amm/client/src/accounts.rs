@@ -0,0 +1,34 @@
+use anchor_lang::{AccountDeserialize, Result};
+use solana_amm::state::{
+    ConcentratedPool, Config, LpPosition, PoolRegistry, Position, RegistryPage, TickArray,
+};
+
+// decode raw account data as fetched from an RPC node, the discriminator is checked
+
+pub fn config(data: &[u8]) -> Result<Config> {
+    Config::try_deserialize(&mut &data[..])
+}
+
+pub fn lp_position(data: &[u8]) -> Result<LpPosition> {
+    LpPosition::try_deserialize(&mut &data[..])
+}
+
+pub fn registry(data: &[u8]) -> Result<PoolRegistry> {
+    PoolRegistry::try_deserialize(&mut &data[..])
+}
+
+pub fn registry_page(data: &[u8]) -> Result<RegistryPage> {
+    RegistryPage::try_deserialize(&mut &data[..])
+}
+
+pub fn concentrated_pool(data: &[u8]) -> Result<ConcentratedPool> {
+    ConcentratedPool::try_deserialize(&mut &data[..])
+}
+
+pub fn tick_array(data: &[u8]) -> Result<TickArray> {
+    TickArray::try_deserialize(&mut &data[..])
+}
+
+pub fn position(data: &[u8]) -> Result<Position> {
+    Position::try_deserialize(&mut &data[..])
+}
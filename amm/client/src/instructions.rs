@@ -0,0 +1,606 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::{associated_token, token, token_2022};
+use solana_amm::state::{ConcentratedPool, Config};
+use solana_amm::{accounts, instruction, ID};
+
+use crate::pda;
+
+// addresses shared by every instruction on one pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub address: Pubkey, // the Config, or the ConcentratedPool
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub token_program_x: Pubkey,
+    pub token_program_y: Pubkey,
+    pub token_program: Pubkey, // owner of the LP mint, unused by concentrated pools
+}
+
+impl PoolKeys {
+    // constant product or StableSwap pool, with its LP mint under the Token program
+    pub fn new(
+        mint_x: Pubkey,
+        mint_y: Pubkey,
+        seed: u64,
+        token_program_x: Pubkey,
+        token_program_y: Pubkey,
+    ) -> Self {
+        Self {
+            address: pda::config(&mint_x, &mint_y, seed).0,
+            mint_x,
+            mint_y,
+            token_program_x,
+            token_program_y,
+            token_program: token::ID,
+        }
+    }
+
+    pub fn concentrated(
+        mint_x: Pubkey,
+        mint_y: Pubkey,
+        seed: u64,
+        token_program_x: Pubkey,
+        token_program_y: Pubkey,
+    ) -> Self {
+        Self {
+            address: pda::concentrated_pool(&mint_x, &mint_y, seed).0,
+            ..Self::new(mint_x, mint_y, seed, token_program_x, token_program_y)
+        }
+    }
+
+    pub fn from_config(address: Pubkey, config: &Config) -> Self {
+        Self {
+            address,
+            mint_x: config.mint_x,
+            mint_y: config.mint_y,
+            token_program_x: config.token_program_x,
+            token_program_y: config.token_program_y,
+            token_program: token::ID,
+        }
+    }
+
+    pub fn from_concentrated(address: Pubkey, pool: &ConcentratedPool) -> Self {
+        Self {
+            address,
+            mint_x: pool.mint_x,
+            mint_y: pool.mint_y,
+            token_program_x: pool.token_program_x,
+            token_program_y: pool.token_program_y,
+            token_program: token::ID,
+        }
+    }
+
+    pub fn mint_lp(&self) -> Pubkey {
+        pda::mint_lp(&self.address).0
+    }
+
+    pub fn vault_x(&self) -> Pubkey {
+        self.ata_x(&self.address)
+    }
+
+    pub fn vault_y(&self) -> Pubkey {
+        self.ata_y(&self.address)
+    }
+
+    pub fn ata_x(&self, owner: &Pubkey) -> Pubkey {
+        pda::token_account(owner, &self.mint_x, &self.token_program_x)
+    }
+
+    pub fn ata_y(&self, owner: &Pubkey) -> Pubkey {
+        pda::token_account(owner, &self.mint_y, &self.token_program_y)
+    }
+
+    pub fn ata_lp(&self, owner: &Pubkey) -> Pubkey {
+        pda::token_account(owner, &self.mint_lp(), &self.token_program)
+    }
+
+    // the owner's token account for X, left out on a native SOL side so it is paid in lamports
+    pub fn user_token_x(&self, owner: &Pubkey) -> Option<Pubkey> {
+        (!is_native(&self.mint_x)).then(|| self.ata_x(owner))
+    }
+
+    pub fn user_token_y(&self, owner: &Pubkey) -> Option<Pubkey> {
+        (!is_native(&self.mint_y)).then(|| self.ata_y(owner))
+    }
+
+    // only passed when a side is native SOL, the program pays it out through this account
+    pub fn wsol(&self, owner: &Pubkey) -> Option<Pubkey> {
+        (is_native(&self.mint_x) || is_native(&self.mint_y)).then(|| pda::wsol(owner).0)
+    }
+}
+
+fn is_native(mint: &Pubkey) -> bool {
+    *mint == token::spl_token::native_mint::ID
+        || *mint == token_2022::spl_token_2022::native_mint::ID
+}
+
+// a concentrated liquidity position and the tick arrays holding its bounds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionKeys {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    pub tick_array_lower: Pubkey,
+    pub tick_array_upper: Pubkey,
+}
+
+impl PositionKeys {
+    pub fn new(
+        pool: &PoolKeys,
+        owner: Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+        tick_spacing: u16,
+    ) -> Self {
+        let tick_array =
+            |tick| pda::tick_array(&pool.address, pda::tick_array_start(tick, tick_spacing)).0;
+        Self {
+            owner,
+            position: pda::position(&pool.address, &owner, tick_lower, tick_upper).0,
+            tick_array_lower: tick_array(tick_lower),
+            tick_array_upper: tick_array(tick_upper),
+        }
+    }
+}
+
+fn build(accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+// `registry_page` is the pair registry's next page, 0 for the first pool of the pair
+pub fn initialize(
+    pool: &PoolKeys,
+    maker: Pubkey,
+    registry_page: u32,
+    args: instruction::Initialize,
+) -> Instruction {
+    let accounts = accounts::Initialize {
+        maker,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        mint_lp: pool.mint_lp(),
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        maker_ata_x: pool.user_token_x(&maker),
+        maker_ata_y: pool.user_token_y(&maker),
+        maker_ata_lp: pool.ata_lp(&maker),
+        vault_lp: pool.ata_lp(&pool.address),
+        config: pool.address,
+        registry: pda::registry(&pool.mint_x, &pool.mint_y).0,
+        registry_page: pda::registry_page(&pool.mint_x, &pool.mint_y, registry_page).0,
+        associated_token_program: associated_token::ID,
+        token_program: pool.token_program,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+// `track_position` passes the user's LpPosition record, creating it if needed
+fn deposit_accounts(pool: &PoolKeys, user: Pubkey, track_position: bool) -> accounts::Deposit {
+    accounts::Deposit {
+        user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        mint_lp: pool.mint_lp(),
+        user_ata_x: pool.user_token_x(&user),
+        user_ata_y: pool.user_token_y(&user),
+        user_ata_lp: pool.ata_lp(&user),
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        config: pool.address,
+        position: track_position.then(|| pda::lp_position(&pool.address, &user).0),
+        associated_token_program: associated_token::ID,
+        token_program: pool.token_program,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn deposit(
+    pool: &PoolKeys,
+    user: Pubkey,
+    track_position: bool,
+    args: instruction::Deposit,
+) -> Instruction {
+    build(deposit_accounts(pool, user, track_position), args)
+}
+
+pub fn deposit_single(
+    pool: &PoolKeys,
+    user: Pubkey,
+    track_position: bool,
+    args: instruction::DepositSingle,
+) -> Instruction {
+    build(deposit_accounts(pool, user, track_position), args)
+}
+
+pub fn swap(pool: &PoolKeys, user: Pubkey, args: instruction::Swap) -> Instruction {
+    let accounts = accounts::Swap {
+        user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        mint_lp: pool.mint_lp(),
+        user_ata_x: pool.user_token_x(&user),
+        user_ata_y: pool.user_token_y(&user),
+        wsol: pool.wsol(&user),
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        config: pool.address,
+        associated_token_program: associated_token::ID,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+// `hops` are the pools in order, with true where the hop sells X. The user's token
+// accounts along the route must already exist.
+pub fn swap_route(
+    user: Pubkey,
+    hops: &[(PoolKeys, bool)],
+    args: instruction::SwapRoute,
+) -> Instruction {
+    let mut ix = build(accounts::SwapRoute { user }, args);
+    for (pool, is_x) in hops {
+        let (mint_in, mint_out, vault_in, vault_out, ata_in, ata_out, program_in, program_out) =
+            match is_x {
+                true => (
+                    pool.mint_x,
+                    pool.mint_y,
+                    pool.vault_x(),
+                    pool.vault_y(),
+                    pool.ata_x(&user),
+                    pool.ata_y(&user),
+                    pool.token_program_x,
+                    pool.token_program_y,
+                ),
+                false => (
+                    pool.mint_y,
+                    pool.mint_x,
+                    pool.vault_y(),
+                    pool.vault_x(),
+                    pool.ata_y(&user),
+                    pool.ata_x(&user),
+                    pool.token_program_y,
+                    pool.token_program_x,
+                ),
+            };
+        ix.accounts.extend([
+            AccountMeta::new(pool.address, false),
+            AccountMeta::new_readonly(pool.mint_lp(), false),
+            AccountMeta::new_readonly(mint_in, false),
+            AccountMeta::new_readonly(mint_out, false),
+            AccountMeta::new(vault_in, false),
+            AccountMeta::new(vault_out, false),
+            AccountMeta::new(ata_in, false),
+            AccountMeta::new(ata_out, false),
+            AccountMeta::new_readonly(program_in, false),
+            AccountMeta::new_readonly(program_out, false),
+        ]);
+    }
+    ix
+}
+
+fn flash_loan_accounts(pool: &PoolKeys, user: Pubkey) -> accounts::FlashLoan {
+    accounts::FlashLoan {
+        user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        mint_lp: pool.mint_lp(),
+        user_ata_x: pool.ata_x(&user),
+        user_ata_y: pool.ata_y(&user),
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        config: pool.address,
+        instructions_sysvar: sysvar::instructions::ID,
+        associated_token_program: associated_token::ID,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+    }
+}
+
+// must be followed by `flash_repay` for the same pool and user in the same transaction
+pub fn flash_borrow(pool: &PoolKeys, user: Pubkey, args: instruction::FlashBorrow) -> Instruction {
+    build(flash_loan_accounts(pool, user), args)
+}
+
+pub fn flash_repay(pool: &PoolKeys, user: Pubkey) -> Instruction {
+    build(flash_loan_accounts(pool, user), instruction::FlashRepay {})
+}
+
+fn withdraw_accounts(pool: &PoolKeys, user: Pubkey, track_position: bool) -> accounts::Withdraw {
+    accounts::Withdraw {
+        user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        mint_lp: pool.mint_lp(),
+        user_ata_x: pool.user_token_x(&user),
+        user_ata_y: pool.user_token_y(&user),
+        user_ata_lp: pool.ata_lp(&user),
+        wsol: pool.wsol(&user),
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        config: pool.address,
+        position: track_position.then(|| pda::lp_position(&pool.address, &user).0),
+        associated_token_program: associated_token::ID,
+        token_program: pool.token_program,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn withdraw(
+    pool: &PoolKeys,
+    user: Pubkey,
+    track_position: bool,
+    args: instruction::Withdraw,
+) -> Instruction {
+    build(withdraw_accounts(pool, user, track_position), args)
+}
+
+pub fn withdraw_single(
+    pool: &PoolKeys,
+    user: Pubkey,
+    track_position: bool,
+    args: instruction::WithdrawSingle,
+) -> Instruction {
+    build(withdraw_accounts(pool, user, track_position), args)
+}
+
+// pool administration, signed by the authority (or the pending one for accept_authority)
+fn update(pool: &PoolKeys, user: Pubkey, args: impl InstructionData) -> Instruction {
+    let accounts = accounts::Update {
+        user,
+        config: pool.address,
+    };
+    build(accounts, args)
+}
+
+pub fn lock(pool: &PoolKeys, authority: Pubkey) -> Instruction {
+    update(pool, authority, instruction::Lock {})
+}
+
+pub fn unlock(pool: &PoolKeys, authority: Pubkey) -> Instruction {
+    update(pool, authority, instruction::Unlock {})
+}
+
+pub fn update_fee(pool: &PoolKeys, authority: Pubkey, args: instruction::UpdateFee) -> Instruction {
+    update(pool, authority, args)
+}
+
+pub fn update_flash_fee(
+    pool: &PoolKeys,
+    authority: Pubkey,
+    args: instruction::UpdateFlashFee,
+) -> Instruction {
+    update(pool, authority, args)
+}
+
+pub fn update_protocol_fee(
+    pool: &PoolKeys,
+    authority: Pubkey,
+    args: instruction::UpdateProtocolFee,
+) -> Instruction {
+    update(pool, authority, args)
+}
+
+pub fn update_treasury(
+    pool: &PoolKeys,
+    authority: Pubkey,
+    args: instruction::UpdateTreasury,
+) -> Instruction {
+    update(pool, authority, args)
+}
+
+pub fn ramp_amp(pool: &PoolKeys, authority: Pubkey, args: instruction::RampAmp) -> Instruction {
+    update(pool, authority, args)
+}
+
+pub fn stop_ramp_amp(pool: &PoolKeys, authority: Pubkey) -> Instruction {
+    update(pool, authority, instruction::StopRampAmp {})
+}
+
+pub fn propose_authority(
+    pool: &PoolKeys,
+    authority: Pubkey,
+    args: instruction::ProposeAuthority,
+) -> Instruction {
+    update(pool, authority, args)
+}
+
+pub fn accept_authority(pool: &PoolKeys, pending_authority: Pubkey) -> Instruction {
+    update(pool, pending_authority, instruction::AcceptAuthority {})
+}
+
+pub fn observe(pool: &PoolKeys, args: instruction::Observe) -> Instruction {
+    let accounts = accounts::Observe {
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        config: pool.address,
+    };
+    build(accounts, args)
+}
+
+pub fn lp_earnings(pool: &PoolKeys, owner: Pubkey) -> Instruction {
+    let accounts = accounts::LpEarnings {
+        config: pool.address,
+        position: pda::lp_position(&pool.address, &owner).0,
+    };
+    build(accounts, instruction::LpEarnings {})
+}
+
+// anyone can pay the fees out, `treasury` must match the pool's
+pub fn collect_protocol_fees(pool: &PoolKeys, user: Pubkey, treasury: Pubkey) -> Instruction {
+    let accounts = accounts::CollectProtocolFees {
+        user,
+        treasury,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        treasury_ata_x: pool.ata_x(&treasury),
+        treasury_ata_y: pool.ata_y(&treasury),
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        config: pool.address,
+        associated_token_program: associated_token::ID,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::CollectProtocolFees {})
+}
+
+// `registry_page` is the pair registry's next page, as for `initialize`
+pub fn initialize_concentrated(
+    pool: &PoolKeys,
+    maker: Pubkey,
+    registry_page: u32,
+    args: instruction::InitializeConcentrated,
+) -> Instruction {
+    let accounts = accounts::InitializeConcentrated {
+        maker,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        pool: pool.address,
+        registry: pda::registry(&pool.mint_x, &pool.mint_y).0,
+        registry_page: pda::registry_page(&pool.mint_x, &pool.mint_y, registry_page).0,
+        associated_token_program: associated_token::ID,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+pub fn init_tick_array(
+    pool: &PoolKeys,
+    payer: Pubkey,
+    args: instruction::InitTickArray,
+) -> Instruction {
+    let accounts = accounts::InitTickArray {
+        payer,
+        pool: pool.address,
+        tick_array: pda::tick_array(&pool.address, args.start_tick).0,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+pub fn open_position(
+    pool: &PoolKeys,
+    owner: Pubkey,
+    args: instruction::OpenPosition,
+) -> Instruction {
+    let accounts = accounts::OpenPosition {
+        owner,
+        pool: pool.address,
+        position: pda::position(&pool.address, &owner, args.tick_lower, args.tick_upper).0,
+        system_program: system_program::ID,
+    };
+    build(accounts, args)
+}
+
+fn modify_liquidity_accounts(
+    pool: &PoolKeys,
+    position: &PositionKeys,
+) -> accounts::ModifyLiquidity {
+    let owner = position.owner;
+    accounts::ModifyLiquidity {
+        owner,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        owner_ata_x: pool.ata_x(&owner),
+        owner_ata_y: pool.ata_y(&owner),
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        pool: pool.address,
+        position: position.position,
+        tick_array_lower: position.tick_array_lower,
+        tick_array_upper: position.tick_array_upper,
+        associated_token_program: associated_token::ID,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn increase_liquidity(
+    pool: &PoolKeys,
+    position: &PositionKeys,
+    args: instruction::IncreaseLiquidity,
+) -> Instruction {
+    build(modify_liquidity_accounts(pool, position), args)
+}
+
+pub fn decrease_liquidity(
+    pool: &PoolKeys,
+    position: &PositionKeys,
+    args: instruction::DecreaseLiquidity,
+) -> Instruction {
+    build(modify_liquidity_accounts(pool, position), args)
+}
+
+pub fn collect_fees(pool: &PoolKeys, position: &PositionKeys) -> Instruction {
+    let owner = position.owner;
+    let accounts = accounts::CollectFees {
+        owner,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        owner_ata_x: pool.ata_x(&owner),
+        owner_ata_y: pool.ata_y(&owner),
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        pool: pool.address,
+        position: position.position,
+        tick_array_lower: position.tick_array_lower,
+        tick_array_upper: position.tick_array_upper,
+        associated_token_program: associated_token::ID,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::CollectFees {})
+}
+
+// `tick_arrays` start with the array holding the current tick and follow the swap direction
+pub fn swap_concentrated(
+    pool: &PoolKeys,
+    user: Pubkey,
+    tick_arrays: &[Pubkey],
+    args: instruction::SwapConcentrated,
+) -> Instruction {
+    let accounts = accounts::SwapConcentrated {
+        user,
+        mint_x: pool.mint_x,
+        mint_y: pool.mint_y,
+        user_ata_x: pool.ata_x(&user),
+        user_ata_y: pool.ata_y(&user),
+        vault_x: pool.vault_x(),
+        vault_y: pool.vault_y(),
+        pool: pool.address,
+        associated_token_program: associated_token::ID,
+        token_program_x: pool.token_program_x,
+        token_program_y: pool.token_program_y,
+        system_program: system_program::ID,
+    };
+    let mut ix = build(accounts, args);
+    ix.accounts.extend(
+        tick_arrays
+            .iter()
+            .map(|tick_array| AccountMeta::new(*tick_array, false)),
+    );
+    ix
+}
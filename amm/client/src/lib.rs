@@ -0,0 +1,10 @@
+// off-chain counterpart of the solana_amm program: addresses, instructions, account
+// decoding and quotes, all built on the program's own types and math
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+pub mod quote;
+
+pub use instructions::{PoolKeys, PositionKeys};
+pub use quote::{quote_deposit, quote_swap, quote_withdraw, TransferFees};
+pub use solana_amm::ID;
@@ -0,0 +1,103 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use solana_amm::state::TICK_ARRAY_SIZE;
+use solana_amm::ID;
+
+// constant product or StableSwap pool
+pub fn config(mint_x: &Pubkey, mint_y: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"amm",
+            mint_x.as_ref(),
+            mint_y.as_ref(),
+            seed.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+}
+
+pub fn mint_lp(config: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"mint_lp", config.as_ref()], &ID)
+}
+
+pub fn lp_position(config: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"lp_position", config.as_ref(), owner.as_ref()], &ID)
+}
+
+// every pool of a pair, mints in canonical order
+pub fn registry(mint_x: &Pubkey, mint_y: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"registry", mint_x.as_ref(), mint_y.as_ref()], &ID)
+}
+
+// one page of the pair's registry, see PoolRegistry::next_page for where new pools go
+pub fn registry_page(mint_x: &Pubkey, mint_y: &Pubkey, page: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"registry",
+            mint_x.as_ref(),
+            mint_y.as_ref(),
+            page.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+}
+
+// mints sorted the way pools expect them, with whether they were swapped
+pub fn canonical_mints(mint_a: Pubkey, mint_b: Pubkey) -> (Pubkey, Pubkey, bool) {
+    match mint_a < mint_b {
+        true => (mint_a, mint_b, false),
+        false => (mint_b, mint_a, true),
+    }
+}
+
+pub fn concentrated_pool(mint_x: &Pubkey, mint_y: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"clmm",
+            mint_x.as_ref(),
+            mint_y.as_ref(),
+            seed.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+}
+
+pub fn tick_array(pool: &Pubkey, start_tick: i32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"tick_array",
+            pool.as_ref(),
+            start_tick.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+}
+
+// start of the tick array holding `tick`
+pub fn tick_array_start(tick: i32, tick_spacing: u16) -> i32 {
+    let span = TICK_ARRAY_SIZE as i32 * tick_spacing as i32;
+    tick.div_euclid(span) * span
+}
+
+pub fn position(pool: &Pubkey, owner: &Pubkey, tick_lower: i32, tick_upper: i32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"position",
+            pool.as_ref(),
+            owner.as_ref(),
+            tick_lower.to_le_bytes().as_ref(),
+            tick_upper.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+}
+
+// temporary wrapped SOL account the program pays native SOL out through
+pub fn wsol(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"wsol", user.as_ref()], &ID)
+}
+
+// vaults and user token accounts are all associated token accounts
+pub fn token_account(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, token_program)
+}
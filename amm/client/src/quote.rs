@@ -0,0 +1,178 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::Mint,
+};
+use solana_amm::curves::{self, SwapQuote, ZapInQuote, ZapOutQuote};
+use solana_amm::errors::AmmError;
+use solana_amm::state::Config;
+
+// The quotes run the same curve code as the instructions, given the pool's Config, the
+// vault balances, the LP supply and the mints' transfer fees as read at the same slot.
+// Amounts are what the user sends and receives, Token-2022 transfer fees included, so they
+// can be passed as the instructions' slippage bounds as they are.
+
+// Token-2022 transfer fees of the pool's two mints for one epoch
+#[derive(Clone, Copy, Default)]
+pub struct TransferFees {
+    pub x: Option<TransferFee>,
+    pub y: Option<TransferFee>,
+}
+
+impl TransferFees {
+    // read from the data of both mint accounts, mints without TransferFee charge nothing
+    pub fn new(mint_x: &[u8], mint_y: &[u8], epoch: u64) -> Result<Self> {
+        Ok(TransferFees {
+            x: epoch_fee(mint_x, epoch)?,
+            y: epoch_fee(mint_y, epoch)?,
+        })
+    }
+
+    // amount that arrives when `amount` of X (or Y) is sent
+    pub fn amount_after_fee(&self, is_x: bool, amount: u64) -> Result<u64> {
+        let fee = match self.side(is_x) {
+            Some(fee) => fee.calculate_fee(amount).ok_or(AmmError::Overflow)?,
+            None => 0,
+        };
+        amount.checked_sub(fee).ok_or(AmmError::Underflow.into())
+    }
+
+    // amount of X (or Y) to send so that exactly `amount` arrives
+    pub fn amount_before_fee(&self, is_x: bool, amount: u64) -> Result<u64> {
+        let fee = match self.side(is_x) {
+            Some(fee) => fee
+                .calculate_inverse_fee(amount)
+                .ok_or(AmmError::Overflow)?,
+            None => 0,
+        };
+        amount.checked_add(fee).ok_or(AmmError::Overflow.into())
+    }
+
+    fn side(&self, is_x: bool) -> Option<&TransferFee> {
+        match is_x {
+            true => self.x.as_ref(),
+            false => self.y.as_ref(),
+        }
+    }
+}
+
+// the fee `mint` charges at `epoch`, the same one the program looks up
+fn epoch_fee(mint: &[u8], epoch: u64) -> Result<Option<TransferFee>> {
+    let state = StateWithExtensions::<Mint>::unpack(mint)?;
+    Ok(state
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .map(|config| *config.get_epoch_fee(epoch)))
+}
+
+// what reaches the user once the output mint's transfer fee is withheld, next to the curve's
+// quote of what leaves the vault
+pub struct Received<Q> {
+    pub quote: Q,
+    pub amount: u64,
+}
+
+// amounts of X and Y a deposit of `amount` LP tokens takes
+pub fn quote_deposit(
+    config: &Config,
+    fees: &TransferFees,
+    vault_x: u64,
+    vault_y: u64,
+    supply: u64,
+    amount: u64,
+) -> Result<(u64, u64)> {
+    let (reserve_x, reserve_y) = reserves(config, vault_x, vault_y, supply)?;
+    let (x, y) = curves::deposit_amounts(reserve_x, reserve_y, supply, amount)?;
+    Ok((
+        fees.amount_before_fee(true, x)?,
+        fees.amount_before_fee(false, y)?,
+    ))
+}
+
+// LP tokens minted for depositing `amount` of one side only
+#[allow(clippy::too_many_arguments)]
+pub fn quote_deposit_single(
+    config: &Config,
+    fees: &TransferFees,
+    vault_x: u64,
+    vault_y: u64,
+    supply: u64,
+    is_x: bool,
+    amount: u64,
+    now: i64,
+) -> Result<ZapInQuote> {
+    let (reserve_x, reserve_y) = reserves(config, vault_x, vault_y, supply)?;
+    let received = fees.amount_after_fee(is_x, amount)?;
+    curves::zap_in(config, reserve_x, reserve_y, supply, is_x, received, now)
+}
+
+// output of swapping `amount` of X (or Y), `now` sets the StableSwap amplification
+#[allow(clippy::too_many_arguments)]
+pub fn quote_swap(
+    config: &Config,
+    fees: &TransferFees,
+    vault_x: u64,
+    vault_y: u64,
+    supply: u64,
+    is_x: bool,
+    amount: u64,
+    now: i64,
+) -> Result<Received<SwapQuote>> {
+    let (reserve_x, reserve_y) = config.reserves(vault_x, vault_y)?;
+    let received = fees.amount_after_fee(is_x, amount)?;
+    let quote = curves::swap(config, reserve_x, reserve_y, supply, is_x, received, now)?;
+    Ok(Received {
+        amount: fees.amount_after_fee(!is_x, quote.withdraw)?,
+        quote,
+    })
+}
+
+// amounts of X and Y paid out for burning `amount` LP tokens
+pub fn quote_withdraw(
+    config: &Config,
+    fees: &TransferFees,
+    vault_x: u64,
+    vault_y: u64,
+    supply: u64,
+    amount: u64,
+) -> Result<(u64, u64)> {
+    let (reserve_x, reserve_y) = reserves(config, vault_x, vault_y, supply)?;
+    let (x, y) = curves::withdraw_amounts(reserve_x, reserve_y, supply, amount)?;
+    Ok((
+        fees.amount_after_fee(true, x)?,
+        fees.amount_after_fee(false, y)?,
+    ))
+}
+
+// amount of one side paid out for burning `amount` LP tokens
+#[allow(clippy::too_many_arguments)]
+pub fn quote_withdraw_single(
+    config: &Config,
+    fees: &TransferFees,
+    vault_x: u64,
+    vault_y: u64,
+    supply: u64,
+    is_x: bool,
+    amount: u64,
+    now: i64,
+) -> Result<Received<ZapOutQuote>> {
+    let (reserve_x, reserve_y) = reserves(config, vault_x, vault_y, supply)?;
+    let quote = curves::zap_out(config, reserve_x, reserve_y, supply, is_x, amount, now)?;
+    Ok(Received {
+        amount: fees.amount_after_fee(is_x, quote.amount_out)?,
+        quote,
+    })
+}
+
+// reserves of a pool that liquidity can be added to or removed from
+fn reserves(config: &Config, vault_x: u64, vault_y: u64, supply: u64) -> Result<(u64, u64)> {
+    let (reserve_x, reserve_y) = config.reserves(vault_x, vault_y)?;
+    require!(
+        supply != 0 && reserve_x != 0 && reserve_y != 0,
+        AmmError::NoLiquidityInPool
+    );
+    Ok((reserve_x, reserve_y))
+}
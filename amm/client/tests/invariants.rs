@@ -0,0 +1,397 @@
+// Property tests on the pool math: random sequences of deposits, swaps and withdrawals are
+// applied to a model pool the way the handlers apply them, and the pool must never lose
+// value per LP token. proptest shrinks any failing sequence to a minimal one.
+//
+// PROPTEST_CASES raises the number of sequences tried.
+
+use anchor_lang::{AccountDeserialize, Space};
+use proptest::prelude::*;
+use solana_amm::curves::{self, stable_swap, MINIMUM_LIQUIDITY};
+use solana_amm::state::{Config, CurveType};
+use solana_amm_client::quote::{self, TransferFees};
+
+// the curves only read the clock to ramp the amplification, which these pools never do
+const NOW: i64 = 0;
+// the modelled mints charge no transfer fee
+const NO_FEES: TransferFees = TransferFees { x: None, y: None };
+
+#[derive(Clone, Copy, Debug)]
+enum Amount {
+    Raw(u64),      // a few units, where rounding matters most
+    Permille(u64), // share of what the operation can take
+}
+
+impl Amount {
+    fn of(self, base: u64) -> u64 {
+        match self {
+            Amount::Raw(amount) => amount,
+            Amount::Permille(permille) => (base as u128 * permille as u128 / 1_000) as u64,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Deposit(Amount),              // of the LP supply
+    DepositSingle(bool, Amount),  // of the reserve deposited into
+    Swap(bool, Amount),           // of the input reserve
+    Withdraw(Amount),             // of the LP tokens that can be burnt
+    WithdrawSingle(bool, Amount), // of the LP tokens that can be burnt
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Params {
+    stable: bool,
+    fee: u16,
+    protocol_fee: u16,
+    amp: u64,
+    x: u64,
+    y: u64,
+}
+
+#[derive(Clone)]
+struct Pool {
+    config: Config,
+    vault_x: u64,
+    vault_y: u64,
+    supply: u64,
+}
+
+impl Pool {
+    fn new(params: Params) -> Pool {
+        // a zeroed account is a valid Config with no authority, ramp or history
+        let data = vec![0u8; 8 + Config::INIT_SPACE];
+        let mut config = Config::try_deserialize_unchecked(&mut &data[..]).unwrap();
+        config.curve = match params.stable {
+            true => CurveType::StableSwap,
+            false => CurveType::ConstantProduct,
+        };
+        config.fee = params.fee;
+        config.protocol_fee = params.protocol_fee;
+        config.amp_initial = params.amp;
+        config.amp_target = params.amp;
+
+        Pool {
+            config,
+            vault_x: params.x,
+            vault_y: params.y,
+            supply: curves::initial_liquidity(params.x, params.y).unwrap(),
+        }
+    }
+
+    fn reserves(&self) -> (u64, u64) {
+        self.config.reserves(self.vault_x, self.vault_y).unwrap()
+    }
+
+    // LP tokens that are not locked in the pool
+    fn burnable(&self) -> u64 {
+        self.supply - MINIMUM_LIQUIDITY
+    }
+
+    // apply `op` as the handler would, or leave the pool untouched where the handler fails
+    fn apply(&mut self, op: Op) {
+        let (reserve_x, reserve_y) = self.reserves();
+        let (vault_x, vault_y, supply) = (self.vault_x, self.vault_y, self.supply);
+
+        match op {
+            Op::Deposit(amount) => {
+                let amount = amount.of(supply);
+                if amount == 0 {
+                    return;
+                }
+                let Ok((x, y)) =
+                    quote::quote_deposit(&self.config, &NO_FEES, vault_x, vault_y, supply, amount)
+                else {
+                    return;
+                };
+                if x == 0 || y == 0 {
+                    return;
+                }
+                self.vault_x += x;
+                self.vault_y += y;
+                self.supply += amount;
+            }
+            Op::DepositSingle(is_x, amount) => {
+                let amount = amount.of(if is_x { reserve_x } else { reserve_y });
+                if amount == 0 {
+                    return;
+                }
+                let Ok(quote) = quote::quote_deposit_single(
+                    &self.config,
+                    &NO_FEES,
+                    vault_x,
+                    vault_y,
+                    supply,
+                    is_x,
+                    amount,
+                    NOW,
+                ) else {
+                    return;
+                };
+                if quote.liquidity == 0 {
+                    return;
+                }
+                self.config
+                    .accrue_fees(quote.swap.fee, is_x, supply)
+                    .unwrap();
+                self.deposit(is_x, amount);
+                self.supply += quote.liquidity;
+            }
+            Op::Swap(is_x, amount) => {
+                let amount = amount.of(if is_x { reserve_x } else { reserve_y });
+                if amount == 0 {
+                    return;
+                }
+                let Ok(quote::Received { quote, .. }) = quote::quote_swap(
+                    &self.config,
+                    &NO_FEES,
+                    vault_x,
+                    vault_y,
+                    supply,
+                    is_x,
+                    amount,
+                    NOW,
+                ) else {
+                    return;
+                };
+                if quote.deposit == 0 || quote.withdraw == 0 {
+                    return;
+                }
+                self.config.accrue_fees(quote.fee, is_x, supply).unwrap();
+                self.deposit(is_x, amount);
+                self.withdraw(!is_x, quote.withdraw);
+            }
+            Op::Withdraw(amount) => {
+                let amount = amount.of(self.burnable()).min(self.burnable());
+                if amount == 0 {
+                    return;
+                }
+                let Ok((x, y)) =
+                    quote::quote_withdraw(&self.config, &NO_FEES, vault_x, vault_y, supply, amount)
+                else {
+                    return;
+                };
+                self.withdraw(true, x);
+                self.withdraw(false, y);
+                self.supply -= amount;
+            }
+            Op::WithdrawSingle(is_x, amount) => {
+                let amount = amount.of(self.burnable()).min(self.burnable());
+                if amount == 0 {
+                    return;
+                }
+                let Ok(quote::Received { quote, .. }) = quote::quote_withdraw_single(
+                    &self.config,
+                    &NO_FEES,
+                    vault_x,
+                    vault_y,
+                    supply,
+                    is_x,
+                    amount,
+                    NOW,
+                ) else {
+                    return;
+                };
+                self.config
+                    .accrue_fees(quote.swap.fee, !is_x, supply - amount)
+                    .unwrap();
+                self.withdraw(is_x, quote.amount_out);
+                self.supply -= amount;
+            }
+        }
+    }
+
+    fn deposit(&mut self, is_x: bool, amount: u64) {
+        match is_x {
+            true => self.vault_x += amount,
+            false => self.vault_y += amount,
+        }
+    }
+
+    fn withdraw(&mut self, is_x: bool, amount: u64) {
+        match is_x {
+            true => self.vault_x -= amount,
+            false => self.vault_y -= amount,
+        }
+    }
+
+    // the curve's invariant over the reserves: x * y, or the StableSwap D
+    fn invariant(&self) -> u128 {
+        let (reserve_x, reserve_y) = self.reserves();
+        match self.config.curve {
+            CurveType::ConstantProduct => reserve_x as u128 * reserve_y as u128,
+            CurveType::StableSwap => {
+                stable_swap::compute_d(self.config.amp_target, reserve_x, reserve_y).unwrap()
+            }
+        }
+    }
+}
+
+// a * b as a 256 bit (hi, lo) pair, to compare products of u128s exactly
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    let mask = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & mask);
+    let (b_hi, b_lo) = (b >> 64, b & mask);
+
+    let lo = a_lo * b_lo;
+    let mid_1 = a_hi * b_lo;
+    let mid_2 = a_lo * b_hi;
+    let (mid, mid_carry) = mid_1.overflowing_add(mid_2);
+    let (lo, lo_carry) = lo.overflowing_add(mid << 64);
+    let hi = a_hi * b_hi + (mid >> 64) + ((mid_carry as u128) << 64) + lo_carry as u128;
+    (hi, lo)
+}
+
+// the invariant per LP token never goes down: x * y / supply^2 for constant product,
+// D / supply for StableSwap, where D is only solved to within one unit
+fn assert_share_value_kept(before: &Pool, after: &Pool, op: Op) {
+    let (k_before, k_after) = (before.invariant(), after.invariant());
+    let (s_before, s_after) = (before.supply as u128, after.supply as u128);
+
+    let kept = match before.config.curve {
+        CurveType::ConstantProduct => {
+            wide_mul(k_after, s_before * s_before) >= wide_mul(k_before, s_after * s_after)
+        }
+        CurveType::StableSwap => wide_mul(k_after + 1, s_before) >= wide_mul(k_before, s_after),
+    };
+    assert!(
+        kept,
+        "{op:?} lost value per LP token: invariant {k_before} -> {k_after}, supply {s_before} -> {s_after}"
+    );
+}
+
+fn assert_pool_valid(pool: &Pool) {
+    let (reserve_x, reserve_y) = pool.reserves();
+    assert!(reserve_x != 0 && reserve_y != 0, "pool drained");
+    assert!(
+        pool.supply >= MINIMUM_LIQUIDITY,
+        "supply under the locked minimum"
+    );
+}
+
+fn amount() -> impl Strategy<Value = Amount> {
+    prop_oneof![
+        (1u64..=10_000).prop_map(Amount::Raw),
+        (1u64..=1_000).prop_map(Amount::Permille),
+    ]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        amount().prop_map(Op::Deposit),
+        (any::<bool>(), amount()).prop_map(|(is_x, a)| Op::DepositSingle(is_x, a)),
+        (any::<bool>(), amount()).prop_map(|(is_x, a)| Op::Swap(is_x, a)),
+        amount().prop_map(Op::Withdraw),
+        (any::<bool>(), amount()).prop_map(|(is_x, a)| Op::WithdrawSingle(is_x, a)),
+    ]
+}
+
+fn params() -> impl Strategy<Value = Params> {
+    // reserves start below 2^40 so the StableSwap solver never overflows
+    let reserve = 10_000u64..=1 << 40;
+    (
+        any::<bool>(),
+        0u16..=1_000,
+        0u16..=10_000,
+        1u64..=10_000,
+        reserve.clone(),
+        reserve,
+    )
+        .prop_map(|(stable, fee, protocol_fee, amp, x, y)| Params {
+            stable,
+            fee,
+            protocol_fee,
+            amp,
+            x,
+            y,
+        })
+}
+
+proptest! {
+    #[test]
+    fn swap_never_lowers_the_invariant(
+        params in params(),
+        swaps in prop::collection::vec((any::<bool>(), amount()), 1..32),
+    ) {
+        let mut pool = Pool::new(params);
+        for (is_x, amount) in swaps {
+            let before = pool.clone();
+            pool.apply(Op::Swap(is_x, amount));
+
+            assert_pool_valid(&pool);
+            prop_assert_eq!(pool.supply, before.supply);
+            match pool.config.curve {
+                CurveType::ConstantProduct => prop_assert!(pool.invariant() >= before.invariant()),
+                CurveType::StableSwap => prop_assert!(pool.invariant() + 1 >= before.invariant()),
+            }
+        }
+    }
+
+    #[test]
+    fn lp_tokens_never_lose_value(
+        params in params(),
+        ops in prop::collection::vec(op(), 1..32),
+    ) {
+        let mut pool = Pool::new(params);
+        for op in ops {
+            let before = pool.clone();
+            pool.apply(op);
+
+            assert_pool_valid(&pool);
+            assert_share_value_kept(&before, &pool, op);
+        }
+    }
+
+    #[test]
+    fn deposit_then_withdraw_returns_no_more(
+        params in params(),
+        ops in prop::collection::vec(op(), 0..16),
+        liquidity in 1u64..=1 << 40,
+    ) {
+        let mut pool = Pool::new(params);
+        for op in ops {
+            pool.apply(op);
+        }
+
+        let (vault_x, vault_y, supply) = (pool.vault_x, pool.vault_y, pool.supply);
+        let Ok((x, y)) = quote::quote_deposit(&pool.config, &NO_FEES, vault_x, vault_y, supply, liquidity)
+        else {
+            return Ok(());
+        };
+        let (out_x, out_y) = quote::quote_withdraw(
+            &pool.config,
+            &NO_FEES,
+            vault_x + x,
+            vault_y + y,
+            supply + liquidity,
+            liquidity,
+        )
+        .unwrap();
+        prop_assert!(out_x <= x && out_y <= y, "paid {x}/{y}, redeemed {out_x}/{out_y}");
+    }
+
+    #[test]
+    fn single_sided_round_trip_returns_no_more(
+        params in params(),
+        is_x in any::<bool>(),
+        amount in amount(),
+    ) {
+        let mut pool = Pool::new(params);
+        let (reserve_x, reserve_y) = pool.reserves();
+        let amount = amount.of(if is_x { reserve_x } else { reserve_y });
+        prop_assume!(amount != 0);
+
+        let minted = pool.supply;
+        pool.apply(Op::DepositSingle(is_x, Amount::Raw(amount)));
+        let liquidity = pool.supply - minted;
+        prop_assume!(liquidity != 0);
+
+        let (vault_x, vault_y) = (pool.vault_x, pool.vault_y);
+        pool.apply(Op::WithdrawSingle(is_x, Amount::Raw(liquidity)));
+        let out = match is_x {
+            true => vault_x - pool.vault_x,
+            false => vault_y - pool.vault_y,
+        };
+        prop_assert!(out <= amount, "deposited {amount}, redeemed {out}");
+    }
+}
@@ -0,0 +1,1080 @@
+// Full pool lifecycle against the program running in-process: no validator, no network.
+// The program is executed natively, the token programs are the builds bundled with
+// solana-program-test.
+
+use anchor_lang::prelude::{AccountInfo, Clock, Pubkey};
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022::{self, extension::ExtensionType};
+use solana_amm::curves::concentrated::{
+    amounts_for_liquidity, fee_growth, mul_shift_64, sqrt_price_at_tick, swap_step,
+};
+use solana_amm::curves::MINIMUM_LIQUIDITY;
+use solana_amm::errors::AmmError;
+use solana_amm::instruction as args;
+use solana_amm::state::{
+    ConcentratedPool, Config, CurveType, PoolKind, PoolRegistry, Position, RegisteredPool,
+    REGISTRY_PAGE_SIZE,
+};
+use solana_amm_client::{
+    accounts, instructions as ix, pda, quote, PoolKeys, PositionKeys, TransferFees,
+};
+use solana_program_test::{
+    processor, BanksClientError, ProgramTest, ProgramTestBanksClientExt, ProgramTestContext,
+};
+use solana_sdk::{
+    entrypoint::ProgramResult,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+
+const NEVER: i64 = i64::MAX;
+
+// anchor's entrypoint ties the accounts to one lifetime, which the test processor can't name
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    solana_amm::entry(program_id, accounts, data)
+}
+
+struct Env {
+    context: ProgramTestContext,
+    pool: PoolKeys,
+    maker: Keypair,
+    user: Keypair,
+}
+
+impl Env {
+    // a constant product pool seeded by `maker`, `user` holds both tokens and no LP yet
+    async fn new(token_program_x: Pubkey, token_program_y: Pubkey) -> Env {
+        Env::with_transfer_fee(token_program_x, token_program_y, 0).await
+    }
+
+    // the same pool with Token-2022 mints withholding `fee_bps` of every transfer
+    async fn with_transfer_fee(
+        token_program_x: Pubkey,
+        token_program_y: Pubkey,
+        fee_bps: u16,
+    ) -> Env {
+        let mut env = Env::start().await;
+        // pools only accept their mints in canonical order
+        let mut mints = [Keypair::new(), Keypair::new()];
+        mints.sort_by_key(|mint| mint.pubkey());
+        let [mint_x, mint_y] = mints;
+        env.create_mint(&mint_x, &token_program_x, fee_bps).await;
+        env.create_mint(&mint_y, &token_program_y, fee_bps).await;
+        env.open(PoolKeys::new(
+            mint_x.pubkey(),
+            mint_y.pubkey(),
+            7,
+            token_program_x,
+            token_program_y,
+        ))
+        .await;
+        env
+    }
+
+    // the same pool against native SOL, which `maker` and `user` pay in lamports
+    async fn native() -> Env {
+        let mut env = Env::start().await;
+        let mint = Keypair::new();
+        env.create_mint(&mint, &spl_token::ID, 0).await;
+        let mut mints = [mint.pubkey(), spl_token::native_mint::ID];
+        mints.sort();
+        env.open(PoolKeys::new(
+            mints[0],
+            mints[1],
+            7,
+            spl_token::ID,
+            spl_token::ID,
+        ))
+        .await;
+        env
+    }
+
+    async fn start() -> Env {
+        let mut program = ProgramTest::new(
+            "solana_amm",
+            solana_amm::ID,
+            processor!(process_instruction),
+        );
+        program.prefer_bpf(false);
+        let context = program.start_with_context().await;
+
+        Env {
+            context,
+            pool: PoolKeys::new(
+                Pubkey::default(),
+                Pubkey::default(),
+                0,
+                spl_token::ID,
+                spl_token::ID,
+            ),
+            maker: Keypair::new(),
+            user: Keypair::new(),
+        }
+    }
+
+    // fund `maker` and `user` with both tokens and seed `pool`
+    async fn open(&mut self, pool: PoolKeys) {
+        self.pool = pool;
+        for owner in [self.maker.pubkey(), self.user.pubkey()] {
+            self.fund(&owner).await;
+            for (mint, is_x) in [(pool.mint_x, true), (pool.mint_y, false)] {
+                if mint != spl_token::native_mint::ID {
+                    self.mint_to(&owner, is_x, 1_000_000_000).await;
+                }
+            }
+        }
+
+        self.initialize(&pool, 7, 30, CurveType::ConstantProduct, 0)
+            .await
+            .unwrap();
+    }
+
+    // `maker` seeds the pool at `seed` with 10M X and 40M Y
+    async fn initialize(
+        &mut self,
+        pool: &PoolKeys,
+        seed: u64,
+        fee: u16,
+        curve: CurveType,
+        amp: u64,
+    ) -> Result<(), BanksClientError> {
+        let maker = self.maker.insecure_clone();
+        let page = match self.registry(pool).await {
+            Some(registry) => registry.next_page(),
+            None => 0,
+        };
+        let initialize = ix::initialize(
+            pool,
+            maker.pubkey(),
+            page,
+            args::Initialize {
+                seed,
+                fee,
+                curve,
+                amp,
+                authority: Some(maker.pubkey()),
+                allowed_extensions: 0,
+                amount_x: 10_000_000,
+                amount_y: 40_000_000,
+            },
+        );
+        self.process(&[initialize], &[&maker]).await
+    }
+
+    // the registry of `pool`'s pair, once its first pool exists
+    async fn registry(&mut self, pool: &PoolKeys) -> Option<PoolRegistry> {
+        let address = pda::registry(&pool.mint_x, &pool.mint_y).0;
+        let account = self
+            .context
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap();
+        account.map(|account| accounts::registry(&account.data).unwrap())
+    }
+
+    // every pool of the pair, read page by page
+    async fn registered_pools(&mut self) -> Vec<RegisteredPool> {
+        let (mint_x, mint_y) = (self.pool.mint_x, self.pool.mint_y);
+        let mut pools = vec![];
+        for page in 0.. {
+            let address = pda::registry_page(&mint_x, &mint_y, page).0;
+            let Some(account) = self
+                .context
+                .banks_client
+                .get_account(address)
+                .await
+                .unwrap()
+            else {
+                return pools;
+            };
+            let page = accounts::registry_page(&account.data).unwrap();
+            pools.extend(page.pools);
+        }
+        unreachable!()
+    }
+
+    async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let blockhash = self
+            .context
+            .banks_client
+            .get_new_latest_blockhash(&self.context.last_blockhash)
+            .await
+            .unwrap();
+        self.context.last_blockhash = blockhash;
+
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    // a non-zero `fee_bps` adds the TransferFee extension, with no cap on the fee
+    async fn create_mint(&mut self, mint: &Keypair, token_program: &Pubkey, fee_bps: u16) {
+        let payer = self.context.payer.pubkey();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let extensions = match fee_bps {
+            0 => vec![],
+            _ => vec![ExtensionType::TransferFeeConfig],
+        };
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions)
+                .unwrap();
+
+        let mut instructions = vec![system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            token_program,
+        )];
+        if fee_bps != 0 {
+            instructions.push(
+                spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+                    token_program,
+                    &mint.pubkey(),
+                    None,
+                    None,
+                    fee_bps,
+                    u64::MAX,
+                )
+                .unwrap(),
+            );
+        }
+        instructions.push(
+            spl_token_2022::instruction::initialize_mint2(
+                token_program,
+                &mint.pubkey(),
+                &payer,
+                None,
+                6,
+            )
+            .unwrap(),
+        );
+        self.process(&instructions, &[mint]).await.unwrap();
+    }
+
+    async fn fund(&mut self, owner: &Pubkey) {
+        let transfer =
+            system_instruction::transfer(&self.context.payer.pubkey(), owner, 10_000_000_000);
+        self.process(&[transfer], &[]).await.unwrap();
+    }
+
+    // create the owner's token account and mint `amount` into it
+    async fn mint_to(&mut self, owner: &Pubkey, is_x: bool, amount: u64) {
+        let (mint, token_program, ata) = match is_x {
+            true => (
+                self.pool.mint_x,
+                self.pool.token_program_x,
+                self.pool.ata_x(owner),
+            ),
+            false => (
+                self.pool.mint_y,
+                self.pool.token_program_y,
+                self.pool.ata_y(owner),
+            ),
+        };
+        let payer = self.context.payer.pubkey();
+
+        let instructions = [
+            create_associated_token_account(&payer, owner, &mint, &token_program),
+            spl_token_2022::instruction::mint_to(&token_program, &mint, &ata, &payer, &[], amount)
+                .unwrap(),
+        ];
+        self.process(&instructions, &[]).await.unwrap();
+    }
+
+    async fn balance(&mut self, token_account: Pubkey) -> u64 {
+        let account = self
+            .context
+            .banks_client
+            .get_account(token_account)
+            .await
+            .unwrap()
+            .unwrap();
+        let len = spl_token::state::Account::LEN;
+        spl_token::state::Account::unpack(&account.data[..len])
+            .unwrap()
+            .amount
+    }
+
+    async fn lp_supply(&mut self) -> u64 {
+        let account = self
+            .context
+            .banks_client
+            .get_account(self.pool.mint_lp())
+            .await
+            .unwrap()
+            .unwrap();
+        spl_token::state::Mint::unpack(&account.data)
+            .unwrap()
+            .supply
+    }
+
+    async fn config(&mut self) -> Config {
+        let account = self
+            .context
+            .banks_client
+            .get_account(self.pool.address)
+            .await
+            .unwrap()
+            .unwrap();
+        accounts::config(&account.data).unwrap()
+    }
+
+    async fn now(&mut self) -> i64 {
+        self.context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+            .unix_timestamp
+    }
+
+    // everything a quote needs, read at the same slot
+    async fn snapshot(&mut self) -> (Config, TransferFees, u64, u64, u64) {
+        let config = self.config().await;
+        let fees = self.transfer_fees().await;
+        let vault_x = self.balance(self.pool.vault_x()).await;
+        let vault_y = self.balance(self.pool.vault_y()).await;
+        let supply = self.lp_supply().await;
+        (config, fees, vault_x, vault_y, supply)
+    }
+
+    async fn transfer_fees(&mut self) -> TransferFees {
+        let mut mints = vec![];
+        for mint in [self.pool.mint_x, self.pool.mint_y] {
+            let account = self.context.banks_client.get_account(mint).await.unwrap();
+            mints.push(account.unwrap().data);
+        }
+        let clock = self
+            .context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap();
+        TransferFees::new(&mints[0], &mints[1], clock.epoch).unwrap()
+    }
+
+    async fn user_balances(&mut self) -> (u64, u64) {
+        let user = self.user.pubkey();
+        (
+            self.balance(self.pool.ata_x(&user)).await,
+            self.balance(self.pool.ata_y(&user)).await,
+        )
+    }
+
+    async fn deposit(
+        &mut self,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64,
+    ) -> Result<(), BanksClientError> {
+        let user = self.user.insecure_clone();
+        let deposit = ix::deposit(
+            &self.pool,
+            user.pubkey(),
+            false,
+            args::Deposit {
+                amount,
+                max_x,
+                max_y,
+                expiration,
+            },
+        );
+        self.process(&[deposit], &[&user]).await
+    }
+
+    async fn swap(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        min: u64,
+        expiration: i64,
+    ) -> Result<(), BanksClientError> {
+        let user = self.user.insecure_clone();
+        let swap = ix::swap(
+            &self.pool,
+            user.pubkey(),
+            args::Swap {
+                is_x,
+                amount,
+                min,
+                expiration,
+            },
+        );
+        self.process(&[swap], &[&user]).await
+    }
+
+    async fn withdraw(
+        &mut self,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64,
+    ) -> Result<(), BanksClientError> {
+        let user = self.user.insecure_clone();
+        let withdraw = ix::withdraw(
+            &self.pool,
+            user.pubkey(),
+            false,
+            args::Withdraw {
+                amount,
+                min_x,
+                min_y,
+                expiration,
+            },
+        );
+        self.process(&[withdraw], &[&user]).await
+    }
+
+    // a concentrated pool at price 1 on the same mints, tick spacing 8 and tick arrays
+    // covering [-128, 256)
+    async fn concentrated(&mut self) -> PoolKeys {
+        let maker = self.maker.insecure_clone();
+        let pool = PoolKeys::concentrated(
+            self.pool.mint_x,
+            self.pool.mint_y,
+            7,
+            self.pool.token_program_x,
+            self.pool.token_program_y,
+        );
+        self.initialize_concentrated(&pool).await.unwrap();
+
+        let instructions = [-128, 0, 128].map(|start_tick| {
+            ix::init_tick_array(&pool, maker.pubkey(), args::InitTickArray { start_tick })
+        });
+        self.process(&instructions, &[&maker]).await.unwrap();
+        pool
+    }
+
+    async fn initialize_concentrated(&mut self, pool: &PoolKeys) -> Result<(), BanksClientError> {
+        let maker = self.maker.insecure_clone();
+        let page = match self.registry(pool).await {
+            Some(registry) => registry.next_page(),
+            None => 0,
+        };
+        let initialize = ix::initialize_concentrated(
+            pool,
+            maker.pubkey(),
+            page,
+            args::InitializeConcentrated {
+                seed: 7,
+                fee: 30,
+                tick_spacing: 8,
+                sqrt_price: 1 << 64,
+                allowed_extensions: 0,
+            },
+        );
+        self.process(&[initialize], &[&maker]).await
+    }
+
+    // open a position of `liquidity` over [tick_lower, tick_upper)
+    async fn open_position(
+        &mut self,
+        pool: &PoolKeys,
+        owner: &Keypair,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+    ) -> PositionKeys {
+        let position = PositionKeys::new(pool, owner.pubkey(), tick_lower, tick_upper, 8);
+        let instructions = [
+            ix::open_position(
+                pool,
+                owner.pubkey(),
+                args::OpenPosition {
+                    tick_lower,
+                    tick_upper,
+                },
+            ),
+            ix::increase_liquidity(
+                pool,
+                &position,
+                args::IncreaseLiquidity {
+                    liquidity,
+                    max_x: u64::MAX,
+                    max_y: u64::MAX,
+                    expiration: NEVER,
+                },
+            ),
+        ];
+        self.process(&instructions, &[owner]).await.unwrap();
+        position
+    }
+
+    async fn swap_concentrated(&mut self, pool: &PoolKeys, is_x: bool, amount: u64) {
+        let user = self.user.insecure_clone();
+        // the array holding the current tick, then the next one in the swap's direction
+        let start = pda::tick_array_start(self.concentrated_pool(pool).await.tick, 8);
+        let step = match is_x {
+            true => -128,
+            false => 128,
+        };
+        let tick_arrays =
+            [start, start + step].map(|start| pda::tick_array(&pool.address, start).0);
+        let swap = ix::swap_concentrated(
+            pool,
+            user.pubkey(),
+            &tick_arrays,
+            args::SwapConcentrated {
+                is_x,
+                amount,
+                min: 1,
+                expiration: NEVER,
+            },
+        );
+        self.process(&[swap], &[&user]).await.unwrap();
+    }
+
+    async fn concentrated_pool(&mut self, pool: &PoolKeys) -> ConcentratedPool {
+        let account = self
+            .context
+            .banks_client
+            .get_account(pool.address)
+            .await
+            .unwrap()
+            .unwrap();
+        accounts::concentrated_pool(&account.data).unwrap()
+    }
+
+    async fn position(&mut self, position: &PositionKeys) -> Position {
+        let account = self
+            .context
+            .banks_client
+            .get_account(position.position)
+            .await
+            .unwrap()
+            .unwrap();
+        accounts::position(&account.data).unwrap()
+    }
+
+    async fn lamports(&mut self, address: Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(address)
+            .await
+            .unwrap()
+    }
+
+    async fn set_locked(&mut self, locked: bool) -> Result<(), BanksClientError> {
+        let maker = self.maker.insecure_clone();
+        let update = match locked {
+            true => ix::lock(&self.pool, maker.pubkey()),
+            false => ix::unlock(&self.pool, maker.pubkey()),
+        };
+        self.process(&[update], &[&maker]).await
+    }
+}
+
+fn assert_amm_error(result: Result<(), BanksClientError>, error: AmmError) {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => assert_eq!(code, u32::from(error), "unexpected error code"),
+        other => panic!("expected {error:?}, got {other:?}"),
+    }
+}
+
+// initialize, then trade checking every transfer against the client's quotes
+async fn lifecycle(token_program_x: Pubkey, token_program_y: Pubkey) {
+    let mut env = Env::new(token_program_x, token_program_y).await;
+    let maker = env.maker.pubkey();
+
+    let (_, _, vault_x, vault_y, supply) = env.snapshot().await;
+    assert_eq!((vault_x, vault_y), (10_000_000, 40_000_000));
+    assert_eq!(supply, 20_000_000);
+    assert_eq!(
+        env.balance(env.pool.ata_lp(&maker)).await,
+        supply - MINIMUM_LIQUIDITY
+    );
+    assert_eq!(
+        env.balance(env.pool.ata_lp(&env.pool.address)).await,
+        MINIMUM_LIQUIDITY
+    );
+
+    trade(&mut env).await;
+}
+
+// deposit, swap both ways and withdraw, every balance matching the client's quotes exactly
+async fn trade(env: &mut Env) {
+    let user = env.user.pubkey();
+
+    // deposit
+    let (config, fees, vault_x, vault_y, supply) = env.snapshot().await;
+    let (x, y) = quote::quote_deposit(&config, &fees, vault_x, vault_y, supply, 1_000_000).unwrap();
+    let before = env.user_balances().await;
+    env.deposit(1_000_000, x, y, NEVER).await.unwrap();
+    assert_eq!(env.user_balances().await, (before.0 - x, before.1 - y));
+    assert_eq!(env.balance(env.pool.ata_lp(&user)).await, 1_000_000);
+    assert_eq!(env.lp_supply().await, supply + 1_000_000);
+
+    // swap X for Y, then Y for X
+    for is_x in [true, false] {
+        let (config, fees, vault_x, vault_y, supply) = env.snapshot().await;
+        let now = env.now().await;
+        let quote = quote::quote_swap(&config, &fees, vault_x, vault_y, supply, is_x, 250_000, now)
+            .unwrap();
+        let before = env.user_balances().await;
+        env.swap(is_x, 250_000, quote.amount, NEVER).await.unwrap();
+
+        let expected = match is_x {
+            true => (before.0 - 250_000, before.1 + quote.amount),
+            false => (before.0 + quote.amount, before.1 - 250_000),
+        };
+        assert_eq!(env.user_balances().await, expected);
+    }
+
+    // withdraw everything the user minted
+    let (config, fees, vault_x, vault_y, supply) = env.snapshot().await;
+    let (x, y) =
+        quote::quote_withdraw(&config, &fees, vault_x, vault_y, supply, 1_000_000).unwrap();
+    let before = env.user_balances().await;
+    env.withdraw(1_000_000, x, y, NEVER).await.unwrap();
+    assert_eq!(env.user_balances().await, (before.0 + x, before.1 + y));
+    assert_eq!(env.balance(env.pool.ata_lp(&user)).await, 0);
+    assert_eq!(env.lp_supply().await, supply - 1_000_000);
+}
+
+#[tokio::test]
+async fn lifecycle_token() {
+    lifecycle(spl_token::ID, spl_token::ID).await;
+}
+
+#[tokio::test]
+async fn lifecycle_token_2022() {
+    lifecycle(spl_token_2022::ID, spl_token_2022::ID).await;
+}
+
+#[tokio::test]
+async fn lifecycle_mixed() {
+    lifecycle(spl_token::ID, spl_token_2022::ID).await;
+}
+
+#[tokio::test]
+async fn quotes_include_transfer_fees() {
+    let mut env = Env::with_transfer_fee(spl_token_2022::ID, spl_token_2022::ID, 150).await;
+    trade(&mut env).await;
+}
+
+#[tokio::test]
+async fn slippage_is_enforced() {
+    let mut env = Env::new(spl_token::ID, spl_token_2022::ID).await;
+
+    let (config, fees, vault_x, vault_y, supply) = env.snapshot().await;
+    let (x, y) = quote::quote_deposit(&config, &fees, vault_x, vault_y, supply, 1_000_000).unwrap();
+    assert_amm_error(
+        env.deposit(1_000_000, x - 1, y, NEVER).await,
+        AmmError::SlippageExceeded,
+    );
+    assert_amm_error(
+        env.deposit(1_000_000, x, y - 1, NEVER).await,
+        AmmError::SlippageExceeded,
+    );
+    env.deposit(1_000_000, x, y, NEVER).await.unwrap();
+
+    let now = env.now().await;
+    let (config, fees, vault_x, vault_y, supply) = env.snapshot().await;
+    let quote =
+        quote::quote_swap(&config, &fees, vault_x, vault_y, supply, true, 250_000, now).unwrap();
+    assert_amm_error(
+        env.swap(true, 250_000, quote.amount + 1, NEVER).await,
+        AmmError::SlippageExceeded,
+    );
+
+    let (x, y) =
+        quote::quote_withdraw(&config, &fees, vault_x, vault_y, supply, 1_000_000).unwrap();
+    assert_amm_error(
+        env.withdraw(1_000_000, x + 1, y, NEVER).await,
+        AmmError::SlippageExceeded,
+    );
+    assert_amm_error(
+        env.withdraw(1_000_000, x, y + 1, NEVER).await,
+        AmmError::SlippageExceeded,
+    );
+    env.withdraw(1_000_000, x, y, NEVER).await.unwrap();
+}
+
+#[tokio::test]
+async fn expired_orders_are_rejected() {
+    let mut env = Env::new(spl_token::ID, spl_token::ID).await;
+    let expired = env.now().await - 1;
+
+    assert_amm_error(
+        env.deposit(1_000_000, u64::MAX, u64::MAX, expired).await,
+        AmmError::OfferExpired,
+    );
+    assert_amm_error(
+        env.swap(true, 250_000, 1, expired).await,
+        AmmError::OfferExpired,
+    );
+
+    env.deposit(1_000_000, u64::MAX, u64::MAX, NEVER)
+        .await
+        .unwrap();
+    assert_amm_error(
+        env.withdraw(1_000_000, 1, 1, expired).await,
+        AmmError::OfferExpired,
+    );
+}
+
+#[tokio::test]
+async fn locked_pool_rejects_trading() {
+    let mut env = Env::new(spl_token::ID, spl_token_2022::ID).await;
+    env.deposit(1_000_000, u64::MAX, u64::MAX, NEVER)
+        .await
+        .unwrap();
+
+    // only the authority can lock the pool
+    let user = env.user.insecure_clone();
+    let lock = ix::lock(&env.pool, user.pubkey());
+    assert_amm_error(
+        env.process(&[lock], &[&user]).await,
+        AmmError::InvalidAuthority,
+    );
+
+    env.set_locked(true).await.unwrap();
+    assert!(env.config().await.locked);
+    assert_amm_error(
+        env.deposit(1_000_000, u64::MAX, u64::MAX, NEVER).await,
+        AmmError::PoolLocked,
+    );
+    assert_amm_error(
+        env.swap(true, 250_000, 1, NEVER).await,
+        AmmError::PoolLocked,
+    );
+    assert_amm_error(
+        env.withdraw(1_000_000, 1, 1, NEVER).await,
+        AmmError::PoolLocked,
+    );
+
+    env.set_locked(false).await.unwrap();
+    env.swap(true, 250_000, 1, NEVER).await.unwrap();
+    env.withdraw(1_000_000, 1, 1, NEVER).await.unwrap();
+}
+
+#[tokio::test]
+async fn registry_lists_every_pool_of_a_pair() {
+    let mut env = Env::new(spl_token::ID, spl_token_2022::ID).await;
+    let (mint_x, mint_y) = (env.pool.mint_x, env.pool.mint_y);
+    let stable = PoolKeys::new(mint_x, mint_y, 8, spl_token::ID, spl_token_2022::ID);
+    env.initialize(&stable, 8, 4, CurveType::StableSwap, 100)
+        .await
+        .unwrap();
+    let concentrated = env.concentrated().await;
+
+    let pool = env.pool;
+    let registry = env.registry(&pool).await.unwrap();
+    assert_eq!((registry.mint_x, registry.mint_y), (mint_x, mint_y));
+    assert_eq!(registry.pools, 3);
+
+    let pools: Vec<_> = env
+        .registered_pools()
+        .await
+        .iter()
+        .map(|pool| (pool.pool, pool.seed, pool.fee, pool.kind))
+        .collect();
+    assert_eq!(
+        pools,
+        [
+            (env.pool.address, 7, 30, PoolKind::ConstantProduct),
+            (stable.address, 8, 4, PoolKind::StableSwap),
+            (concentrated.address, 7, 30, PoolKind::Concentrated),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn registry_pages_never_fill_up() {
+    let mut env = Env::new(spl_token::ID, spl_token::ID).await;
+    let (mint_x, mint_y) = (env.pool.mint_x, env.pool.mint_y);
+    let count = REGISTRY_PAGE_SIZE as u64 + 1;
+
+    // every pool is seeded with 40M Y, more than the maker was given
+    let (maker, payer) = (env.maker.pubkey(), env.context.payer.pubkey());
+    let mint = spl_token::instruction::mint_to(
+        &spl_token::ID,
+        &mint_y,
+        &env.pool.ata_y(&maker),
+        &payer,
+        &[],
+        40_000_000 * count,
+    )
+    .unwrap();
+    env.process(&[mint], &[]).await.unwrap();
+
+    for seed in 8..7 + count {
+        let pool = PoolKeys::new(mint_x, mint_y, seed, spl_token::ID, spl_token::ID);
+        env.initialize(&pool, seed, 30, CurveType::ConstantProduct, 0)
+            .await
+            .unwrap();
+    }
+
+    let pool = env.pool;
+    assert_eq!(env.registry(&pool).await.unwrap().pools, count as u32);
+    let seeds: Vec<_> = env
+        .registered_pools()
+        .await
+        .iter()
+        .map(|pool| pool.seed)
+        .collect();
+    assert_eq!(seeds, (7..7 + count).collect::<Vec<_>>());
+
+    let address = pda::registry_page(&mint_x, &mint_y, 1).0;
+    let account = env.context.banks_client.get_account(address).await.unwrap();
+    let page = accounts::registry_page(&account.unwrap().data).unwrap();
+    assert_eq!((page.page, page.pools.len()), (1, 1));
+}
+
+#[tokio::test]
+async fn reversed_mints_are_rejected() {
+    let mut env = Env::new(spl_token::ID, spl_token_2022::ID).await;
+    let reversed = PoolKeys::new(
+        env.pool.mint_y,
+        env.pool.mint_x,
+        7,
+        spl_token_2022::ID,
+        spl_token::ID,
+    );
+    assert_amm_error(
+        env.initialize(&reversed, 7, 30, CurveType::ConstantProduct, 0)
+            .await,
+        AmmError::InvalidMintOrder,
+    );
+
+    let reversed = PoolKeys::concentrated(
+        env.pool.mint_y,
+        env.pool.mint_x,
+        7,
+        spl_token_2022::ID,
+        spl_token::ID,
+    );
+    assert_amm_error(
+        env.initialize_concentrated(&reversed).await,
+        AmmError::InvalidMintOrder,
+    );
+}
+
+#[tokio::test]
+async fn native_sol_is_paid_in_lamports() {
+    let mut env = Env::native().await;
+    let user = env.user.insecure_clone();
+    let native_is_x = env.pool.mint_x == spl_token::native_mint::ID;
+
+    // wrapped SOL the user already holds must never be touched by the pool
+    let wsol_ata = pda::token_account(&user.pubkey(), &spl_token::native_mint::ID, &spl_token::ID);
+    let payer = env.context.payer.pubkey();
+    let wrap = [
+        create_associated_token_account(
+            &payer,
+            &user.pubkey(),
+            &spl_token::native_mint::ID,
+            &spl_token::ID,
+        ),
+        system_instruction::transfer(&user.pubkey(), &wsol_ata, 5_000_000),
+        spl_token::instruction::sync_native(&spl_token::ID, &wsol_ata).unwrap(),
+    ];
+    env.process(&wrap, &[&user]).await.unwrap();
+
+    let (config, fees, vault_x, vault_y, supply) = env.snapshot().await;
+    let (x, y) = quote::quote_deposit(&config, &fees, vault_x, vault_y, supply, 1_000_000).unwrap();
+    env.deposit(1_000_000, x, y, NEVER).await.unwrap();
+    assert_eq!(env.snapshot().await.2, vault_x + x);
+    assert_eq!(env.snapshot().await.3, vault_y + y);
+
+    // buy native SOL, it arrives as lamports
+    let (config, fees, vault_x, vault_y, supply) = env.snapshot().await;
+    let now = env.now().await;
+    let quote = quote::quote_swap(
+        &config,
+        &fees,
+        vault_x,
+        vault_y,
+        supply,
+        !native_is_x,
+        250_000,
+        now,
+    )
+    .unwrap();
+    let before = env.lamports(user.pubkey()).await;
+    env.swap(!native_is_x, 250_000, quote.amount, NEVER)
+        .await
+        .unwrap();
+    assert_eq!(env.lamports(user.pubkey()).await, before + quote.amount);
+
+    // withdraw, the native share arrives as lamports too
+    let (config, fees, vault_x, vault_y, supply) = env.snapshot().await;
+    let (x, y) =
+        quote::quote_withdraw(&config, &fees, vault_x, vault_y, supply, 1_000_000).unwrap();
+    let before = env.lamports(user.pubkey()).await;
+    env.withdraw(1_000_000, x, y, NEVER).await.unwrap();
+    let native = match native_is_x {
+        true => x,
+        false => y,
+    };
+    assert_eq!(env.lamports(user.pubkey()).await, before + native);
+
+    // the temporary account is gone and the user's own wrapped SOL is intact
+    let temporary = pda::wsol(&user.pubkey()).0;
+    assert!(env
+        .context
+        .banks_client
+        .get_account(temporary)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(env.balance(wsol_ata).await, 5_000_000);
+}
+
+// open, add to, trade against, shrink and collect a position, every transfer matching the
+// concentrated liquidity math
+#[tokio::test]
+async fn concentrated_lifecycle() {
+    let mut env = Env::new(spl_token::ID, spl_token_2022::ID).await;
+    let pool = env.concentrated().await;
+    let user = env.user.insecure_clone();
+    let (lower, upper) = (
+        sqrt_price_at_tick(-64).unwrap(),
+        sqrt_price_at_tick(64).unwrap(),
+    );
+    let liquidity = 10_000_000_000;
+
+    // open_position and increase_liquidity
+    let (x, y) = amounts_for_liquidity(1 << 64, lower, upper, liquidity, true).unwrap();
+    let before = env.user_balances().await;
+    let position = env.open_position(&pool, &user, -64, 64, liquidity).await;
+    assert_eq!(env.user_balances().await, (before.0 - x, before.1 - y));
+
+    let increase = ix::increase_liquidity(
+        &pool,
+        &position,
+        args::IncreaseLiquidity {
+            liquidity,
+            max_x: x,
+            max_y: y,
+            expiration: NEVER,
+        },
+    );
+    env.process(&[increase], &[&user]).await.unwrap();
+    assert_eq!(
+        env.user_balances().await,
+        (before.0 - 2 * x, before.1 - 2 * y)
+    );
+    assert_eq!(env.position(&position).await.liquidity, 2 * liquidity);
+    assert_eq!(env.concentrated_pool(&pool).await.liquidity, 2 * liquidity);
+
+    // swap_concentrated, within the position's range
+    let step = swap_step(true, 1 << 64, lower, 2 * liquidity, 100_000, 30).unwrap();
+    let before = env.user_balances().await;
+    env.swap_concentrated(&pool, true, 100_000).await;
+    assert_eq!(
+        env.user_balances().await,
+        (before.0 - 100_000, before.1 + step.amount_out as u64)
+    );
+    let state = env.concentrated_pool(&pool).await;
+    assert_eq!(state.sqrt_price, step.sqrt_price);
+    assert_eq!(
+        state.fee_growth_x,
+        fee_growth(step.fee, 2 * liquidity).unwrap()
+    );
+
+    // decrease_liquidity pays the tokens back, the fees wait for collect_fees
+    let (x, y) = amounts_for_liquidity(state.sqrt_price, lower, upper, liquidity, false).unwrap();
+    let decrease = ix::decrease_liquidity(
+        &pool,
+        &position,
+        args::DecreaseLiquidity {
+            liquidity,
+            min_x: x,
+            min_y: y,
+            expiration: NEVER,
+        },
+    );
+    let before = env.user_balances().await;
+    env.process(&[decrease], &[&user]).await.unwrap();
+    assert_eq!(env.user_balances().await, (before.0 + x, before.1 + y));
+
+    let fees = mul_shift_64(2 * liquidity, state.fee_growth_x).unwrap() as u64;
+    assert_eq!(env.position(&position).await.fees_owed_x, fees);
+    let before = env.user_balances().await;
+    env.process(&[ix::collect_fees(&pool, &position)], &[&user])
+        .await
+        .unwrap();
+    assert_eq!(env.user_balances().await, (before.0 + fees, before.1));
+    assert_eq!(env.position(&position).await.fees_owed_x, 0);
+}
+
+#[tokio::test]
+async fn closed_positions_collect_only_their_fees() {
+    let mut env = Env::new(spl_token::ID, spl_token::ID).await;
+    let pool = env.concentrated().await;
+    let (maker, user) = (env.maker.insecure_clone(), env.user.insecure_clone());
+
+    // fees accrue to a wide position before the user's narrower one exists
+    env.open_position(&pool, &maker, -128, 128, 10_000_000_000)
+        .await;
+    env.swap_concentrated(&pool, true, 100_000).await;
+    env.swap_concentrated(&pool, false, 100_000).await;
+
+    let liquidity = 10_000_000_000;
+    let position = env.open_position(&pool, &user, -64, 64, liquidity).await;
+    let opened = env.concentrated_pool(&pool).await;
+    env.swap_concentrated(&pool, true, 100_000).await;
+    let swapped = env.concentrated_pool(&pool).await;
+    assert!(swapped.tick >= -64, "the price left the position's range");
+
+    // the user's ticks are emptied by the decrease, the fees must be settled against them first
+    let decrease = ix::decrease_liquidity(
+        &pool,
+        &position,
+        args::DecreaseLiquidity {
+            liquidity,
+            min_x: 0,
+            min_y: 0,
+            expiration: NEVER,
+        },
+    );
+    env.process(&[decrease], &[&user]).await.unwrap();
+
+    let before = env.user_balances().await;
+    env.process(&[ix::collect_fees(&pool, &position)], &[&user])
+        .await
+        .unwrap();
+    let after = env.user_balances().await;
+
+    let earned = |growth: u128, since: u128| {
+        mul_shift_64(liquidity, growth.wrapping_sub(since)).unwrap() as u64
+    };
+    assert_eq!(
+        (after.0 - before.0, after.1 - before.1),
+        (
+            earned(swapped.fee_growth_x, opened.fee_growth_x),
+            earned(swapped.fee_growth_y, opened.fee_growth_y),
+        )
+    );
+    assert_ne!(after.0, before.0);
+}
@@ -0,0 +1,151 @@
+use crate::errors::AmmError;
+use crate::events::PositionFeesCollected;
+use crate::state::{ConcentratedPool, Position, TickArray};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+			init_if_needed,
+			payer = owner,
+			associated_token::mint = mint_x,
+			associated_token::authority = owner,
+			associated_token::token_program = token_program_x,
+		)]
+    pub owner_ata_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			init_if_needed,
+			payer = owner,
+			associated_token::mint = mint_y,
+			associated_token::authority = owner,
+			associated_token::token_program = token_program_y,
+		)]
+    pub owner_ata_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_x,
+			associated_token::authority = pool,
+			associated_token::token_program = token_program_x,
+		)]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_y,
+			associated_token::authority = pool,
+			associated_token::token_program = token_program_y,
+		)]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			has_one = mint_x,
+			has_one = mint_y,
+			seeds = [b"clmm", mint_x.key().as_ref(), mint_y.key().as_ref(), pool.seed.to_le_bytes().as_ref()],
+			bump = pool.bump
+		)]
+    pub pool: Box<Account<'info, ConcentratedPool>>,
+
+    #[account(
+			mut,
+			has_one = pool,
+			has_one = owner,
+		)]
+    pub position: Box<Account<'info, Position>>,
+
+    #[account(has_one = pool)]
+    pub tick_array_lower: Box<Account<'info, TickArray>>,
+
+    #[account(has_one = pool)]
+    pub tick_array_upper: Box<Account<'info, TickArray>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = pool.token_program_x)]
+    pub token_program_x: Interface<'info, TokenInterface>,
+    #[account(address = pool.token_program_y)]
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CollectFees<'info> {
+    pub fn collect_fees(&mut self) -> Result<()> {
+        let (inside_x, inside_y) = self.pool.position_fee_growth_inside(
+            &self.position,
+            &self.tick_array_lower,
+            &self.tick_array_upper,
+        )?;
+        self.position.update_fees(inside_x, inside_y)?;
+
+        let (fees_x, fees_y) = (self.position.fees_owed_x, self.position.fees_owed_y);
+        require!(fees_x != 0 || fees_y != 0, AmmError::ZeroBalance);
+
+        self.position.fees_owed_x = 0;
+        self.position.fees_owed_y = 0;
+
+        if fees_x != 0 {
+            self.withdraw_tokens(fees_x, true)?;
+        }
+        if fees_y != 0 {
+            self.withdraw_tokens(fees_y, false)?;
+        }
+
+        emit!(PositionFeesCollected {
+            pool: self.pool.key(),
+            position: self.position.key(),
+            owner: self.owner.key(),
+            amount_x: fees_x,
+            amount_y: fees_y,
+        });
+        Ok(())
+    }
+
+    pub fn withdraw_tokens(&self, amount: u64, is_x: bool) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),
+                self.owner_ata_x.to_account_info(),
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.vault_y.to_account_info(),
+                self.owner_ata_y.to_account_info(),
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to,
+            mint: mint.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let seed = self.pool.seed.to_le_bytes();
+
+        let seeds = &[
+            b"clmm",
+            self.mint_x.to_account_info().key.as_ref(),
+            self.mint_y.to_account_info().key.as_ref(),
+            seed.as_ref(),
+            &[self.pool.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(token_program, accounts, signer_seeds);
+
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+}
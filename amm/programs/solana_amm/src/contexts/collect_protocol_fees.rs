@@ -0,0 +1,137 @@
+use crate::assert_no_flash_loan;
+use crate::{errors::AmmError, events::ProtocolFeesCollected, state::Config};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+// anyone can collect, the fees only ever go to the treasury's token accounts
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: only used as the authority of the treasury token accounts
+    #[account(address = config.treasury)]
+    pub treasury: UncheckedAccount<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+			init_if_needed,
+			payer = user,
+			associated_token::mint = mint_x,
+			associated_token::authority = treasury,
+			associated_token::token_program = token_program_x,
+		)]
+    pub treasury_ata_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			init_if_needed,
+			payer = user,
+			associated_token::mint = mint_y,
+			associated_token::authority = treasury,
+			associated_token::token_program = token_program_y,
+		)]
+    pub treasury_ata_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_x,
+			associated_token::authority = config,
+			associated_token::token_program = token_program_x,
+		)]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_y,
+			associated_token::authority = config,
+			associated_token::token_program = token_program_y,
+		)]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			has_one = mint_x,
+			has_one = mint_y,
+			seeds = [b"amm", mint_x.key().as_ref(), mint_y.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+			bump = config.bump
+		)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = config.token_program_x)]
+    pub token_program_x: Interface<'info, TokenInterface>,
+    #[account(address = config.token_program_y)]
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CollectProtocolFees<'info> {
+    pub fn collect_protocol_fees(&mut self) -> Result<()> {
+        assert_no_flash_loan!(self.config.flash_loan_active);
+
+        let (fees_x, fees_y) = (self.config.protocol_fees_x, self.config.protocol_fees_y);
+        require!(fees_x != 0 || fees_y != 0, AmmError::ZeroBalance);
+
+        self.config.protocol_fees_x = 0;
+        self.config.protocol_fees_y = 0;
+
+        if fees_x != 0 {
+            self.withdraw_tokens(fees_x, true)?;
+        }
+        if fees_y != 0 {
+            self.withdraw_tokens(fees_y, false)?;
+        }
+
+        emit!(ProtocolFeesCollected {
+            pool: self.config.key(),
+            treasury: self.config.treasury,
+            amount_x: fees_x,
+            amount_y: fees_y,
+        });
+        Ok(())
+    }
+
+    pub fn withdraw_tokens(&self, amount: u64, is_x: bool) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),
+                self.treasury_ata_x.to_account_info(),
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.vault_y.to_account_info(),
+                self.treasury_ata_y.to_account_info(),
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to,
+            mint: mint.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+
+        let seed = self.config.seed.to_le_bytes();
+
+        let seeds = &[
+            b"amm",
+            self.mint_x.to_account_info().key.as_ref(),
+            self.mint_y.to_account_info().key.as_ref(),
+            seed.as_ref(),
+            &[self.config.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(token_program, accounts, signer_seeds);
+
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+}
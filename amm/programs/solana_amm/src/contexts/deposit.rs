@@ -1,6 +1,10 @@
-use crate::state::Config;
+use crate::curves;
+use crate::events::LiquidityAdded;
+use crate::helpers::extensions::{amount_after_fee, amount_before_fee};
+use crate::helpers::native::{user_token_account, wrap_sol};
+use crate::state::{Config, LpPosition};
+use crate::{assert_no_flash_loan, assert_not_expired, assert_not_locked};
 use crate::{assert_non_zero, errors::AmmError};
-use crate::{assert_not_expired, assert_not_locked};
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
@@ -8,7 +12,6 @@ use anchor_spl::{
         mint_to, transfer_checked, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
     },
 };
-use constant_product_curve::ConstantProduct;
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
@@ -25,20 +28,22 @@ pub struct Deposit<'info> {
     pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-			mut,
+			init_if_needed,
+			payer = user,
 			associated_token::mint = mint_x,
 			associated_token::authority = user,
-			associated_token::token_program = token_program,
+			associated_token::token_program = token_program_x,
 		)]
-    pub user_ata_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub user_ata_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
-			mut,
+			init_if_needed,
+			payer = user,
 			associated_token::mint = mint_y,
 			associated_token::authority = user,
-			associated_token::token_program = token_program,
+			associated_token::token_program = token_program_y,
 		)]
-    pub user_ata_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub user_ata_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
 			init_if_needed,
@@ -53,78 +58,227 @@ pub struct Deposit<'info> {
 			mut,
 			associated_token::mint = mint_x,
 			associated_token::authority = config,
-			associated_token::token_program = token_program,
+			associated_token::token_program = token_program_x,
 		)]
     pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
 			mut,
 			associated_token::mint = mint_y,
-			associated_token::authority = user,
-			associated_token::token_program = token_program,
+			associated_token::authority = config,
+			associated_token::token_program = token_program_y,
 		)]
     pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
+			mut,
 			has_one = mint_x,
 			has_one = mint_y,
 			seeds= [b"amm", mint_x.key().as_ref(), mint_y.key().as_ref(), config.seed.to_le_bytes().as_ref()],
-			bump
+			bump = config.bump
 		)]
     pub config: Box<Account<'info, Config>>,
 
+    // optional fee reporting record, created on the first deposit that passes it
+    #[account(
+			init_if_needed,
+			payer = user,
+			space = 8 + LpPosition::INIT_SPACE,
+			seeds = [b"lp_position", config.key().as_ref(), user.key().as_ref()],
+			bump
+		)]
+    pub position: Option<Box<Account<'info, LpPosition>>>,
+
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
+    #[account(address = config.token_program_x)]
+    pub token_program_x: Interface<'info, TokenInterface>,
+    #[account(address = config.token_program_y)]
+    pub token_program_y: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Deposit<'info> {
-    pub fn deposit(&self, amount: u64, max_x: u64, max_y: u64, expiration: i64) -> Result<()> {
+    pub fn deposit(
+        &mut self,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64,
+        position_bump: Option<u8>,
+    ) -> Result<()> {
         assert_non_zero!([amount, max_x, max_y]);
         assert_not_locked!(self.config.locked);
+        assert_no_flash_loan!(self.config.flash_loan_active);
         assert_not_expired!(expiration);
 
-        let (x, y) = match self.mint_lp.supply == 0
-            && self.vault_x.amount == 0
-            && self.vault_y.amount == 0
-        {
-            true => (max_x, max_y),
-            false => {
-                let amounts = ConstantProduct::xy_deposit_amounts_from_l(
-                    self.vault_x.amount,
-                    self.vault_y.amount,
-                    self.mint_lp.supply,
-                    amount,
-                    6,
-                )
-                .map_err(AmmError::from)?;
-                (amounts.x, amounts.y)
-            }
-        };
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+        self.config.update_oracle(reserve_x, reserve_y)?;
+
+        // initialize seeds the pool and locks MINIMUM_LIQUIDITY, so the supply is never zero
+        require!(
+            self.mint_lp.supply != 0 && reserve_x != 0 && reserve_y != 0,
+            AmmError::NoLiquidityInPool
+        );
+
+        let (x, y) = curves::deposit_amounts(reserve_x, reserve_y, self.mint_lp.supply, amount)?;
+
+        // the vaults must receive x and y, so gross them up by any transfer fee
+        let x = amount_before_fee(&self.mint_x.to_account_info(), x)?;
+        let y = amount_before_fee(&self.mint_y.to_account_info(), y)?;
 
         // Check for slippage
         require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
         self.deposit_tokens(x, true)?;
         self.deposit_tokens(y, false)?;
-        self.mint_lp_token(amount)
+        self.mint_lp_token(amount)?;
+        self.track_position(amount, position_bump)?;
+        self.emit_liquidity_added(x, y, amount)
+    }
+
+    pub fn deposit_single(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        min_lp_out: u64,
+        expiration: i64,
+        position_bump: Option<u8>,
+    ) -> Result<()> {
+        assert_non_zero!([amount]);
+        assert_not_locked!(self.config.locked);
+        assert_no_flash_loan!(self.config.flash_loan_active);
+        assert_not_expired!(expiration);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+        self.config.update_oracle(reserve_x, reserve_y)?;
+        require!(
+            self.mint_lp.supply != 0 && reserve_x != 0 && reserve_y != 0,
+            AmmError::NoLiquidityInPool
+        );
+
+        let mint_in = match is_x {
+            true => self.mint_x.to_account_info(),
+            false => self.mint_y.to_account_info(),
+        };
+
+        // the whole deposit lands in one vault, part of it is swapped on paper
+        let received = amount_after_fee(&mint_in, amount)?;
+        let quote = curves::zap_in(
+            &self.config,
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            is_x,
+            received,
+            Clock::get()?.unix_timestamp,
+        )?;
+        assert_non_zero!([quote.liquidity]);
+
+        // Check for slippage
+        require!(quote.liquidity >= min_lp_out, AmmError::SlippageExceeded);
+        self.config
+            .accrue_fees(quote.swap.fee, is_x, self.mint_lp.supply)?;
+
+        self.deposit_tokens(amount, is_x)?;
+        self.mint_lp_token(quote.liquidity)?;
+        self.track_position(quote.liquidity, position_bump)?;
+
+        let (x, y) = match is_x {
+            true => (amount, 0),
+            false => (0, amount),
+        };
+        self.emit_liquidity_added(x, y, quote.liquidity)
+    }
+
+    // add `minted` LP tokens to the depositor's position record, when one was passed
+    pub fn track_position(&mut self, minted: u64, bump: Option<u8>) -> Result<()> {
+        let Some(position) = self.position.as_mut() else {
+            return Ok(());
+        };
+
+        // just created by init_if_needed
+        if position.owner == Pubkey::default() {
+            position.set_inner(LpPosition {
+                config: self.config.key(),
+                owner: self.user.key(),
+                liquidity: 0,
+                fee_per_share_x: self.config.fee_per_share_x,
+                fee_per_share_y: self.config.fee_per_share_y,
+                fees_earned_x: 0,
+                fees_earned_y: 0,
+                opened_at: Clock::get()?.unix_timestamp,
+                bump: bump.ok_or(AmmError::BumpError)?,
+            });
+        }
+
+        position.checkpoint(&self.config)?;
+        position.liquidity = position
+            .liquidity
+            .checked_add(minted)
+            .ok_or(AmmError::Overflow)?;
+        Ok(())
+    }
+
+    // read back the vaults and the LP supply once the transfers are done
+    pub fn emit_liquidity_added(
+        &mut self,
+        amount_x: u64,
+        amount_y: u64,
+        lp_amount: u64,
+    ) -> Result<()> {
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.mint_lp.reload()?;
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        emit!(LiquidityAdded {
+            pool: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount,
+            reserve_x,
+            reserve_y,
+            lp_supply: self.mint_lp.supply,
+        });
+        Ok(())
     }
 
     pub fn deposit_tokens(&self, amount: u64, is_x: bool) -> Result<()> {
         assert_non_zero!([amount]);
 
-        let (from, to, mint) = match is_x {
+        let (from, to, mint, token_program) = match is_x {
             true => (
-                self.user_ata_x.to_account_info(),
+                &self.user_ata_x,
                 self.vault_x.to_account_info(),
                 self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
             ),
             false => (
-                self.user_ata_y.to_account_info(),
+                &self.user_ata_y,
                 self.vault_y.to_account_info(),
                 self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
             ),
         };
 
+        // without a token account native SOL is wrapped straight into the vault
+        let Some(from) = user_token_account(from, &mint.key())? else {
+            return wrap_sol(
+                self.user.to_account_info(),
+                to,
+                amount,
+                self.system_program.to_account_info(),
+                token_program,
+            );
+        };
+
         let accounts = TransferChecked {
             from,
             to,
@@ -132,8 +286,7 @@ impl<'info> Deposit<'info> {
             authority: self.user.to_account_info(),
         };
 
-        let cpi_ctx: CpiContext<TransferChecked> =
-            CpiContext::new(self.token_program.to_account_info(), accounts);
+        let cpi_ctx: CpiContext<TransferChecked> = CpiContext::new(token_program, accounts);
 
         transfer_checked(cpi_ctx, amount, mint.decimals)?;
 
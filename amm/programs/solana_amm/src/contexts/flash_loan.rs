@@ -0,0 +1,233 @@
+use crate::events::{FlashBorrowed, FlashRepaid};
+use crate::helpers::extensions::amount_before_fee;
+use crate::state::Config;
+use crate::{assert_no_flash_loan, assert_not_locked};
+use crate::{assert_non_zero, errors::AmmError};
+use anchor_lang::{prelude::*, Discriminator};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+use solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+
+// flash_borrow and flash_repay share these accounts, which is how the borrow
+// recognises its repayment later in the same transaction
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+			seeds = [b"mint_lp", config.key().as_ref()],
+			bump = config.lp_bump
+		)]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+			init_if_needed,
+			payer = user,
+			associated_token::mint = mint_x,
+			associated_token::authority = user,
+			associated_token::token_program = token_program_x,
+		)]
+    pub user_ata_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			init_if_needed,
+			payer = user,
+			associated_token::mint = mint_y,
+			associated_token::authority = user,
+			associated_token::token_program = token_program_y,
+		)]
+    pub user_ata_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_x,
+			associated_token::authority = config,
+			associated_token::token_program = token_program_x,
+		)]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_y,
+			associated_token::authority = config,
+			associated_token::token_program = token_program_y,
+		)]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			has_one = mint_x,
+			has_one = mint_y,
+			seeds = [b"amm", mint_x.key().as_ref(), mint_y.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+			bump = config.bump
+		)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+        address = solana_program::sysvar::instructions::ID
+    )]
+    /// CHECK: This is the instructions sysvar account
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = config.token_program_x)]
+    pub token_program_x: Interface<'info, TokenInterface>,
+    #[account(address = config.token_program_y)]
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FlashLoan<'info> {
+    pub fn flash_borrow(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        assert_non_zero!([amount]);
+        assert_not_locked!(self.config.locked);
+        assert_no_flash_loan!(self.config.flash_loan_active);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+        let reserve = match is_x {
+            true => reserve_x,
+            false => reserve_y,
+        };
+        require!(amount < reserve, AmmError::InsufficientBalance);
+
+        self.verify_repayment()?;
+
+        self.config.flash_loan_active = true;
+        self.config.flash_loan_is_x = is_x;
+        self.config.flash_loan_amount = amount;
+
+        self.withdraw_tokens(amount, is_x)?;
+        emit!(FlashBorrowed {
+            pool: self.config.key(),
+            user: self.user.key(),
+            is_x,
+            amount,
+        });
+        Ok(())
+    }
+
+    pub fn flash_repay(&mut self) -> Result<()> {
+        require!(self.config.flash_loan_active, AmmError::NoFlashLoan);
+
+        let (is_x, amount) = (self.config.flash_loan_is_x, self.config.flash_loan_amount);
+        let fee = (amount as u128 * self.config.flash_fee as u128).div_ceil(10_000) as u64;
+        let owed = amount.checked_add(fee).ok_or(AmmError::Overflow)?;
+
+        let mint = match is_x {
+            true => self.mint_x.to_account_info(),
+            false => self.mint_y.to_account_info(),
+        };
+        // the vault has to get back the full amount plus fee, the fee stays with the LPs
+        let owed = amount_before_fee(&mint, owed)?;
+
+        self.config.flash_loan_active = false;
+        self.config.flash_loan_is_x = false;
+        self.config.flash_loan_amount = 0;
+        self.config.accrue_lp_fee(fee, is_x, self.mint_lp.supply)?;
+
+        self.deposit_tokens(owed, is_x)?;
+        emit!(FlashRepaid {
+            pool: self.config.key(),
+            user: self.user.key(),
+            is_x,
+            amount,
+            fee,
+        });
+        Ok(())
+    }
+
+    // a later instruction in this transaction must be flash_repay on the same accounts
+    fn verify_repayment(&self) -> Result<()> {
+        let sysvar = self.instructions_sysvar.to_account_info();
+        let current_index = load_current_index_checked(&sysvar)? as usize;
+        let current = load_instruction_at_checked(current_index, &sysvar)?;
+
+        let mut index = current_index + 1;
+        while let Ok(ix) = load_instruction_at_checked(index, &sysvar) {
+            if ix.program_id == crate::ID
+                && ix.data.get(..8) == Some(&crate::instruction::FlashRepay::DISCRIMINATOR[..])
+                && ix.accounts == current.accounts
+            {
+                return Ok(());
+            }
+            index += 1;
+        }
+        err!(AmmError::FlashLoanNotRepaid)
+    }
+
+    pub fn deposit_tokens(&self, amount: u64, is_x: bool) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                self.user_ata_x.to_account_info(),
+                self.vault_x.to_account_info(),
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.user_ata_y.to_account_info(),
+                self.vault_y.to_account_info(),
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to,
+            mint: mint.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(token_program, accounts);
+
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+
+    pub fn withdraw_tokens(&self, amount: u64, is_x: bool) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),
+                self.user_ata_x.to_account_info(),
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.vault_y.to_account_info(),
+                self.user_ata_y.to_account_info(),
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to,
+            mint: mint.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+
+        let seed = self.config.seed.to_le_bytes();
+
+        let seeds = &[
+            b"amm",
+            self.mint_x.to_account_info().key.as_ref(),
+            self.mint_y.to_account_info().key.as_ref(),
+            seed.as_ref(),
+            &[self.config.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(token_program, accounts, signer_seeds);
+
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+}
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::curves::concentrated::{MAX_TICK, MIN_TICK};
+use crate::{
+    errors::AmmError,
+    state::{ConcentratedPool, Tick, TickArray, TICK_ARRAY_SIZE},
+};
+
+#[derive(Accounts)]
+#[instruction(start_tick: i32)]
+pub struct InitTickArray<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+			seeds = [b"clmm", pool.mint_x.as_ref(), pool.mint_y.as_ref(), pool.seed.to_le_bytes().as_ref()],
+			bump = pool.bump
+		)]
+    pub pool: Box<Account<'info, ConcentratedPool>>,
+
+    #[account(
+			init,
+			payer = payer,
+			space = 8 + TickArray::INIT_SPACE,
+			seeds = [b"tick_array", pool.key().as_ref(), start_tick.to_le_bytes().as_ref()],
+			bump
+		)]
+    pub tick_array: Box<Account<'info, TickArray>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitTickArray<'info> {
+    pub fn init_tick_array(&mut self, start_tick: i32) -> Result<()> {
+        let span = self.pool.tick_array_span();
+        require!(
+            start_tick % span == 0 && start_tick <= MAX_TICK && start_tick + span > MIN_TICK,
+            AmmError::InvalidTickArray
+        );
+
+        self.tick_array.set_inner(TickArray {
+            pool: self.pool.key(),
+            start_tick,
+            ticks: [Tick::default(); TICK_ARRAY_SIZE],
+        });
+        Ok(())
+    }
+}
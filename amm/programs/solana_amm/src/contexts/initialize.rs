@@ -6,7 +6,20 @@ use anchor_spl::{
     },
 };
 
-use crate::state::Config;
+use crate::curves::{
+    initial_liquidity,
+    stable_swap::{MAX_AMP, MIN_AMP},
+    MINIMUM_LIQUIDITY,
+};
+use crate::{
+    errors::AmmError,
+    events::PoolInitialized,
+    helpers::extensions::check_mint_extensions,
+    helpers::native::{user_token_account, wrap_sol},
+    state::{
+        Config, CurveType, Observation, PoolRegistry, RegisteredPool, RegistryPage, OBSERVATIONS,
+    },
+};
 
 #[derive(Accounts)]
 #[instruction(seed: u64)]
@@ -15,12 +28,12 @@ pub struct Initialize<'info> {
     pub maker: Signer<'info>,
 
     #[account(
-			mint::token_program = token_program
+			mint::token_program = token_program_x
 		)]
     pub mint_x: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-			mint::token_program = token_program
+			mint::token_program = token_program_y
 		)]
     pub mint_y: Box<InterfaceAccount<'info, Mint>>,
 
@@ -30,7 +43,7 @@ pub struct Initialize<'info> {
 			mint::authority = config,
 			mint::decimals = 6,
 			mint::token_program = token_program,
-			seeds = [b"mint", config.key().as_ref()],
+			seeds = [b"mint_lp", config.key().as_ref()],
 			bump
 
 		)]
@@ -41,7 +54,7 @@ pub struct Initialize<'info> {
 			payer = maker,
 			associated_token::mint = mint_x,
 			associated_token::authority = config,
-			associated_token::token_program = token_program
+			associated_token::token_program = token_program_x
 		)]
     pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
@@ -50,25 +63,27 @@ pub struct Initialize<'info> {
 			payer = maker,
 			associated_token::mint = mint_y,
 			associated_token::authority = config,
-			associated_token::token_program = token_program
+			associated_token::token_program = token_program_y
 		)]
     pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-			mut,
+			init_if_needed,
+			payer = maker,
 			associated_token::mint = mint_x,
 			associated_token::authority = maker,
-			associated_token::token_program = token_program
+			associated_token::token_program = token_program_x
 		)]
-    pub maker_ata_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub maker_ata_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
-			mut,
+			init_if_needed,
+			payer = maker,
 			associated_token::mint = mint_y,
 			associated_token::authority = maker,
-			associated_token::token_program = token_program
+			associated_token::token_program = token_program_y
 		)]
-    pub maker_ata_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub maker_ata_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
 			init,
@@ -79,47 +94,163 @@ pub struct Initialize<'info> {
 		)]
     pub maker_ata_lp: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    // holds MINIMUM_LIQUIDITY forever, nothing can move it out
+    #[account(
+			init,
+			payer = maker,
+			associated_token::mint = mint_lp,
+			associated_token::authority = config,
+			associated_token::token_program = token_program
+		)]
+    pub vault_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
     #[account(
 			init,
 			payer = maker,
 			space = 8 + Config::INIT_SPACE,
-			seeds = [b"config", mint_x.key().as_ref(), mint_y.key().as_ref(), seed.to_le_bytes().as_ref()],
+			seeds = [b"amm", mint_x.key().as_ref(), mint_y.key().as_ref(), seed.to_le_bytes().as_ref()],
 			bump
 		)]
     pub config: Box<Account<'info, Config>>,
+
+    #[account(
+			init_if_needed,
+			payer = maker,
+			space = 8 + PoolRegistry::INIT_SPACE,
+			seeds = [b"registry", mint_x.key().as_ref(), mint_y.key().as_ref()],
+			bump
+		)]
+    pub registry: Box<Account<'info, PoolRegistry>>,
+
+    #[account(
+			init_if_needed,
+			payer = maker,
+			space = 8 + RegistryPage::INIT_SPACE,
+			seeds = [b"registry", mint_x.key().as_ref(), mint_y.key().as_ref(), registry.next_page().to_le_bytes().as_ref()],
+			bump
+		)]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Initialize<'info> {
-    pub fn init(&mut self, seed: u64, fee: u16, bump: u8, lp_bump: u8) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        &mut self,
+        seed: u64,
+        fee: u16,
+        curve: CurveType,
+        amp: u64,
+        authority: Option<Pubkey>,
+        allowed_extensions: u8,
+        bump: u8,
+        lp_bump: u8,
+        registry_bumps: (u8, u8),
+    ) -> Result<()> {
+        // one ordering per pair, so X/Y and Y/X pools can't both exist
+        require!(
+            self.mint_x.key() < self.mint_y.key(),
+            AmmError::InvalidMintOrder
+        );
+        require!(fee < 10_000, AmmError::InvalidFee);
+        let amp = match curve {
+            CurveType::ConstantProduct => 0,
+            CurveType::StableSwap => {
+                require!(
+                    (MIN_AMP..=MAX_AMP).contains(&amp),
+                    AmmError::InvalidAmplification
+                );
+                amp
+            }
+        };
+        check_mint_extensions(&self.mint_x.to_account_info(), allowed_extensions)?;
+        check_mint_extensions(&self.mint_y.to_account_info(), allowed_extensions)?;
+
         self.config.set_inner(Config {
+            authority,
+            pending_authority: None,
             mint_x: self.mint_x.key(),
             mint_y: self.mint_y.key(),
+            token_program_x: self.token_program_x.key(),
+            token_program_y: self.token_program_y.key(),
             bump,
             lp_bump,
             seed,
+            curve,
+            amp_initial: amp,
+            amp_target: amp,
+            amp_ramp_start: 0,
+            amp_ramp_end: 0,
             fee,
+            protocol_fee: 0,
+            treasury: self.maker.key(),
+            flash_fee: fee,
+            protocol_fees_x: 0,
+            protocol_fees_y: 0,
+            fee_per_share_x: 0,
+            fee_per_share_y: 0,
+            allowed_extensions,
+            price_x_cumulative: 0,
+            price_y_cumulative: 0,
+            last_update: 0,
+            observations: [Observation::default(); OBSERVATIONS],
+            observation_index: 0,
             locked: false,
+            flash_loan_active: false,
+            flash_loan_is_x: false,
+            flash_loan_amount: 0,
         });
-        Ok(())
+        self.config.init_oracle()?;
+        self.register(registry_bumps)
+    }
+
+    fn register(&mut self, bumps: (u8, u8)) -> Result<()> {
+        let key = self.registry.key();
+        self.registry.register(
+            key,
+            (self.mint_x.key(), self.mint_y.key()),
+            &mut self.registry_page,
+            RegisteredPool {
+                pool: self.config.key(),
+                seed: self.config.seed,
+                fee: self.config.fee,
+                kind: self.config.curve.into(),
+            },
+            bumps,
+        )
     }
 
     pub fn deposit(&mut self, amount: u64, is_x: bool) -> Result<()> {
-        let (from, to, mint) = match is_x {
+        let (from, to, mint, token_program) = match is_x {
             true => (
-                self.maker_ata_x.to_account_info(),
+                &self.maker_ata_x,
                 self.vault_x.to_account_info(),
                 self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
             ),
             false => (
-                self.maker_ata_y.to_account_info(),
+                &self.maker_ata_y,
                 self.vault_y.to_account_info(),
                 self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
             ),
         };
 
+        // without a token account native SOL is wrapped straight into the vault
+        let Some(from) = user_token_account(from, &mint.key())? else {
+            return wrap_sol(
+                self.maker.to_account_info(),
+                to,
+                amount,
+                self.system_program.to_account_info(),
+                token_program,
+            );
+        };
+
         let accounts = TransferChecked {
             from,
             mint: mint.to_account_info(),
@@ -127,21 +258,45 @@ impl<'info> Initialize<'info> {
             authority: self.maker.to_account_info(),
         };
 
-        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), accounts);
+        let cpi_ctx = CpiContext::new(token_program, accounts);
         transfer_checked(cpi_ctx, amount, mint.decimals)
     }
 
-    pub fn mint_lp(&mut self, amount_x: u64, amount_y: u64) -> Result<()> {
-        // calculating the amount of LP tokens to mint
-        // based on the product of the two token amounts being deposited (amount_x and amount_y)
-        // checked_mul() is used to prevent overflow, Some(amount) is returned if successful, None is returned if overflow occurs
-        let amount = amount_x
-            .checked_mul(amount_y)
-            .ok_or(ProgramError::ArithmeticOverflow)?;
+    pub fn mint_lp(&mut self) -> Result<()> {
+        // read back what actually arrived, Token-2022 transfer fees may have been withheld
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        let (amount_x, amount_y) = (self.vault_x.amount, self.vault_y.amount);
+
+        // the first LP supply is the geometric mean of the deposits,
+        // MINIMUM_LIQUIDITY of it is locked in the pool for good
+        let liquidity = initial_liquidity(amount_x, amount_y)?;
 
+        self.mint_lp_to(MINIMUM_LIQUIDITY, self.vault_lp.to_account_info())?;
+        self.mint_lp_to(
+            liquidity - MINIMUM_LIQUIDITY,
+            self.maker_ata_lp.to_account_info(),
+        )?;
+
+        emit!(PoolInitialized {
+            pool: self.config.key(),
+            maker: self.maker.key(),
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            seed: self.config.seed,
+            fee: self.config.fee,
+            curve: self.config.curve,
+            reserve_x: amount_x,
+            reserve_y: amount_y,
+            lp_supply: liquidity,
+        });
+        Ok(())
+    }
+
+    pub fn mint_lp_to(&self, amount: u64, to: AccountInfo<'info>) -> Result<()> {
         let accounts = MintTo {
             mint: self.mint_lp.to_account_info(),
-            to: self.maker_ata_lp.to_account_info(),
+            to,
             authority: self.config.to_account_info(),
         };
 
@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::curves::concentrated::{
+    max_sqrt_price, min_sqrt_price, tick_at_sqrt_price, MAX_TICK_SPACING,
+};
+use crate::{
+    errors::AmmError,
+    events::ConcentratedPoolInitialized,
+    helpers::extensions::check_mint_extensions,
+    state::{ConcentratedPool, PoolKind, PoolRegistry, RegisteredPool, RegistryPage},
+};
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct InitializeConcentrated<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    #[account(
+			mint::token_program = token_program_x
+		)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+			mint::token_program = token_program_y
+		)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+			init,
+			payer = maker,
+			associated_token::mint = mint_x,
+			associated_token::authority = pool,
+			associated_token::token_program = token_program_x
+		)]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			init,
+			payer = maker,
+			associated_token::mint = mint_y,
+			associated_token::authority = pool,
+			associated_token::token_program = token_program_y
+		)]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			init,
+			payer = maker,
+			space = 8 + ConcentratedPool::INIT_SPACE,
+			seeds = [b"clmm", mint_x.key().as_ref(), mint_y.key().as_ref(), seed.to_le_bytes().as_ref()],
+			bump
+		)]
+    pub pool: Box<Account<'info, ConcentratedPool>>,
+
+    #[account(
+			init_if_needed,
+			payer = maker,
+			space = 8 + PoolRegistry::INIT_SPACE,
+			seeds = [b"registry", mint_x.key().as_ref(), mint_y.key().as_ref()],
+			bump
+		)]
+    pub registry: Box<Account<'info, PoolRegistry>>,
+
+    #[account(
+			init_if_needed,
+			payer = maker,
+			space = 8 + RegistryPage::INIT_SPACE,
+			seeds = [b"registry", mint_x.key().as_ref(), mint_y.key().as_ref(), registry.next_page().to_le_bytes().as_ref()],
+			bump
+		)]
+    pub registry_page: Box<Account<'info, RegistryPage>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeConcentrated<'info> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        &mut self,
+        seed: u64,
+        fee: u16,
+        tick_spacing: u16,
+        sqrt_price: u128,
+        allowed_extensions: u8,
+        bump: u8,
+        registry_bumps: (u8, u8),
+    ) -> Result<()> {
+        // one ordering per pair, so X/Y and Y/X pools can't both exist
+        require!(
+            self.mint_x.key() < self.mint_y.key(),
+            AmmError::InvalidMintOrder
+        );
+        require!(fee < 10_000, AmmError::InvalidFee);
+        require!(
+            tick_spacing != 0 && tick_spacing <= MAX_TICK_SPACING,
+            AmmError::InvalidTick
+        );
+        require!(
+            sqrt_price >= min_sqrt_price() && sqrt_price < max_sqrt_price(),
+            AmmError::InvalidPrice
+        );
+        check_mint_extensions(&self.mint_x.to_account_info(), allowed_extensions)?;
+        check_mint_extensions(&self.mint_y.to_account_info(), allowed_extensions)?;
+
+        self.pool.set_inner(ConcentratedPool {
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            token_program_x: self.token_program_x.key(),
+            token_program_y: self.token_program_y.key(),
+            seed,
+            fee,
+            tick_spacing,
+            sqrt_price,
+            tick: tick_at_sqrt_price(sqrt_price)?,
+            liquidity: 0,
+            fee_growth_x: 0,
+            fee_growth_y: 0,
+            allowed_extensions,
+            bump,
+        });
+
+        emit!(ConcentratedPoolInitialized {
+            pool: self.pool.key(),
+            maker: self.maker.key(),
+            mint_x: self.pool.mint_x,
+            mint_y: self.pool.mint_y,
+            seed,
+            fee,
+            tick_spacing,
+            sqrt_price,
+            tick: self.pool.tick,
+        });
+        self.register(registry_bumps)
+    }
+
+    fn register(&mut self, bumps: (u8, u8)) -> Result<()> {
+        let key = self.registry.key();
+        self.registry.register(
+            key,
+            (self.mint_x.key(), self.mint_y.key()),
+            &mut self.registry_page,
+            RegisteredPool {
+                pool: self.pool.key(),
+                seed: self.pool.seed,
+                fee: self.pool.fee,
+                kind: PoolKind::Concentrated,
+            },
+            bumps,
+        )
+    }
+}
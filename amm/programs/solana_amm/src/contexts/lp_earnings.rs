@@ -0,0 +1,24 @@
+use crate::state::{Config, Earnings, LpPosition};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct LpEarnings<'info> {
+    #[account(
+			seeds = [b"amm", config.mint_x.as_ref(), config.mint_y.as_ref(), config.seed.to_le_bytes().as_ref()],
+			bump = config.bump
+		)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+			has_one = config,
+			seeds = [b"lp_position", config.key().as_ref(), position.owner.as_ref()],
+			bump = position.bump
+		)]
+    pub position: Box<Account<'info, LpPosition>>,
+}
+
+impl<'info> LpEarnings<'info> {
+    pub fn lp_earnings(&self) -> Result<Earnings> {
+        self.position.earnings(&self.config)
+    }
+}
@@ -2,4 +2,46 @@ pub mod initialize;
 pub use initialize::*;
 
 pub mod deposit;
-pub use deposit::*;
\ No newline at end of file
+pub use deposit::*;
+
+pub mod swap;
+pub use swap::*;
+
+pub mod withdraw;
+pub use withdraw::*;
+
+pub mod update;
+pub use update::*;
+
+pub mod collect_protocol_fees;
+pub use collect_protocol_fees::*;
+
+pub mod observe;
+pub use observe::*;
+
+pub mod swap_route;
+pub use swap_route::*;
+
+pub mod lp_earnings;
+pub use lp_earnings::*;
+
+pub mod flash_loan;
+pub use flash_loan::*;
+
+pub mod initialize_concentrated;
+pub use initialize_concentrated::*;
+
+pub mod init_tick_array;
+pub use init_tick_array::*;
+
+pub mod open_position;
+pub use open_position::*;
+
+pub mod modify_liquidity;
+pub use modify_liquidity::*;
+
+pub mod collect_fees;
+pub use collect_fees::*;
+
+pub mod swap_concentrated;
+pub use swap_concentrated::*;
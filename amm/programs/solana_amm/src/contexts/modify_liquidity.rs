@@ -0,0 +1,289 @@
+use crate::curves::concentrated::{amounts_for_liquidity, sqrt_price_at_tick};
+use crate::events::PositionLiquidityChanged;
+use crate::helpers::extensions::{amount_after_fee, amount_before_fee};
+use crate::state::{add_delta, ConcentratedPool, Position, TickArray};
+use crate::{assert_not_expired, errors::AmmError};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+#[derive(Accounts)]
+pub struct ModifyLiquidity<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+			init_if_needed,
+			payer = owner,
+			associated_token::mint = mint_x,
+			associated_token::authority = owner,
+			associated_token::token_program = token_program_x,
+		)]
+    pub owner_ata_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			init_if_needed,
+			payer = owner,
+			associated_token::mint = mint_y,
+			associated_token::authority = owner,
+			associated_token::token_program = token_program_y,
+		)]
+    pub owner_ata_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_x,
+			associated_token::authority = pool,
+			associated_token::token_program = token_program_x,
+		)]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_y,
+			associated_token::authority = pool,
+			associated_token::token_program = token_program_y,
+		)]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			has_one = mint_x,
+			has_one = mint_y,
+			seeds = [b"clmm", mint_x.key().as_ref(), mint_y.key().as_ref(), pool.seed.to_le_bytes().as_ref()],
+			bump = pool.bump
+		)]
+    pub pool: Box<Account<'info, ConcentratedPool>>,
+
+    #[account(
+			mut,
+			has_one = pool,
+			has_one = owner,
+		)]
+    pub position: Box<Account<'info, Position>>,
+
+    // may be the same account as tick_array_upper when both ticks share an array
+    #[account(
+			mut,
+			has_one = pool,
+		)]
+    pub tick_array_lower: Box<Account<'info, TickArray>>,
+
+    #[account(
+			mut,
+			has_one = pool,
+		)]
+    pub tick_array_upper: Box<Account<'info, TickArray>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = pool.token_program_x)]
+    pub token_program_x: Interface<'info, TokenInterface>,
+    #[account(address = pool.token_program_y)]
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ModifyLiquidity<'info> {
+    pub fn increase_liquidity(
+        &mut self,
+        liquidity: u128,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        require!(liquidity != 0, AmmError::ZeroBalance);
+        require!(liquidity <= i128::MAX as u128, AmmError::Overflow);
+        assert_not_expired!(expiration);
+
+        let (x, y) = self.amounts(liquidity, true)?;
+        self.modify_position(liquidity as i128)?;
+
+        // the vaults must receive x and y, so gross them up by any transfer fee
+        let x = amount_before_fee(&self.mint_x.to_account_info(), x)?;
+        let y = amount_before_fee(&self.mint_y.to_account_info(), y)?;
+
+        // Check for slippage
+        require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
+        self.deposit_tokens(x, true)?;
+        self.deposit_tokens(y, false)?;
+        self.emit_liquidity_changed(liquidity as i128, x, y);
+        Ok(())
+    }
+
+    pub fn decrease_liquidity(
+        &mut self,
+        liquidity: u128,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        require!(liquidity != 0, AmmError::ZeroBalance);
+        require!(liquidity <= i128::MAX as u128, AmmError::Overflow);
+        assert_not_expired!(expiration);
+
+        let (x, y) = self.amounts(liquidity, false)?;
+        self.modify_position(-(liquidity as i128))?;
+
+        // Check for slippage on what the owner receives
+        require!(
+            amount_after_fee(&self.mint_x.to_account_info(), x)? >= min_x
+                && amount_after_fee(&self.mint_y.to_account_info(), y)? >= min_y,
+            AmmError::SlippageExceeded
+        );
+        self.withdraw_tokens(x, true)?;
+        self.withdraw_tokens(y, false)?;
+        self.emit_liquidity_changed(-(liquidity as i128), x, y);
+        Ok(())
+    }
+
+    fn emit_liquidity_changed(&self, liquidity_delta: i128, amount_x: u64, amount_y: u64) {
+        emit!(PositionLiquidityChanged {
+            pool: self.pool.key(),
+            position: self.position.key(),
+            owner: self.owner.key(),
+            liquidity_delta,
+            amount_x,
+            amount_y,
+            position_liquidity: self.position.liquidity,
+            pool_liquidity: self.pool.liquidity,
+        });
+    }
+
+    // tokens backing `liquidity` over the position's range, rounded in the pool's favour
+    fn amounts(&self, liquidity: u128, round_up: bool) -> Result<(u64, u64)> {
+        let (x, y) = amounts_for_liquidity(
+            self.pool.sqrt_price,
+            sqrt_price_at_tick(self.position.tick_lower)?,
+            sqrt_price_at_tick(self.position.tick_upper)?,
+            liquidity,
+            round_up,
+        )?;
+        require!(x != 0 || y != 0, AmmError::InvalidAmount);
+        Ok((x, y))
+    }
+
+    // add `delta` liquidity to the position, its bounding ticks and, when in range, the pool
+    fn modify_position(&mut self, delta: i128) -> Result<()> {
+        let (tick_lower, tick_upper) = (self.position.tick_lower, self.position.tick_upper);
+        let spacing = self.pool.tick_spacing;
+        let same = self.tick_array_lower.key() == self.tick_array_upper.key();
+
+        let lower = self.tick_array_lower.tick_index(tick_lower, spacing)?;
+        self.tick_array_lower.ticks[lower].update(tick_lower, &self.pool, delta, false)?;
+
+        let array_upper = match same {
+            true => &mut self.tick_array_lower,
+            false => &mut self.tick_array_upper,
+        };
+        let upper = array_upper.tick_index(tick_upper, spacing)?;
+        array_upper.ticks[upper].update(tick_upper, &self.pool, delta, true)?;
+
+        // both fields serialize into the same account on exit, keep them identical
+        if same {
+            self.tick_array_upper.ticks = self.tick_array_lower.ticks;
+        }
+
+        let (inside_x, inside_y) = self.pool.position_fee_growth_inside(
+            &self.position,
+            &self.tick_array_lower,
+            &self.tick_array_upper,
+        )?;
+        self.position.update_fees(inside_x, inside_y)?;
+        self.position.liquidity = add_delta(self.position.liquidity, delta)?;
+
+        if self.pool.is_active(tick_lower, tick_upper) {
+            self.pool.liquidity = add_delta(self.pool.liquidity, delta)?;
+        }
+
+        // the fees are settled, ticks the position was the last to use can go
+        self.tick_array_lower.ticks[lower].clear_if_unused();
+        match same {
+            true => {
+                self.tick_array_lower.ticks[upper].clear_if_unused();
+                self.tick_array_upper.ticks = self.tick_array_lower.ticks;
+            }
+            false => self.tick_array_upper.ticks[upper].clear_if_unused(),
+        }
+        Ok(())
+    }
+
+    pub fn deposit_tokens(&self, amount: u64, is_x: bool) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                self.owner_ata_x.to_account_info(),
+                self.vault_x.to_account_info(),
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.owner_ata_y.to_account_info(),
+                self.vault_y.to_account_info(),
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to,
+            mint: mint.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(token_program, accounts);
+
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+
+    pub fn withdraw_tokens(&self, amount: u64, is_x: bool) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),
+                self.owner_ata_x.to_account_info(),
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.vault_y.to_account_info(),
+                self.owner_ata_y.to_account_info(),
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to,
+            mint: mint.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let seed = self.pool.seed.to_le_bytes();
+
+        let seeds = &[
+            b"clmm",
+            self.mint_x.to_account_info().key.as_ref(),
+            self.mint_y.to_account_info().key.as_ref(),
+            seed.as_ref(),
+            &[self.pool.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(token_program, accounts, signer_seeds);
+
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+}
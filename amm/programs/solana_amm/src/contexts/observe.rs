@@ -0,0 +1,41 @@
+use crate::assert_no_flash_loan;
+use crate::errors::AmmError;
+use crate::state::{Config, Twap};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    #[account(
+			associated_token::mint = config.mint_x,
+			associated_token::authority = config,
+			associated_token::token_program = config.token_program_x,
+		)]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			associated_token::mint = config.mint_y,
+			associated_token::authority = config,
+			associated_token::token_program = config.token_program_y,
+		)]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			seeds = [b"amm", config.mint_x.as_ref(), config.mint_y.as_ref(), config.seed.to_le_bytes().as_ref()],
+			bump = config.bump
+		)]
+    pub config: Box<Account<'info, Config>>,
+}
+
+impl<'info> Observe<'info> {
+    pub fn observe(&self, window: i64) -> Result<Twap> {
+        // the vaults don't reflect the reserves while a loan is out
+        assert_no_flash_loan!(self.config.flash_loan_active);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        self.config.observe(reserve_x, reserve_y, window)
+    }
+}
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::AmmError,
+    events::PositionOpened,
+    state::{ConcentratedPool, Position},
+};
+
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+			seeds = [b"clmm", pool.mint_x.as_ref(), pool.mint_y.as_ref(), pool.seed.to_le_bytes().as_ref()],
+			bump = pool.bump
+		)]
+    pub pool: Box<Account<'info, ConcentratedPool>>,
+
+    #[account(
+			init,
+			payer = owner,
+			space = 8 + Position::INIT_SPACE,
+			seeds = [
+				b"position",
+				pool.key().as_ref(),
+				owner.key().as_ref(),
+				tick_lower.to_le_bytes().as_ref(),
+				tick_upper.to_le_bytes().as_ref()
+			],
+			bump
+		)]
+    pub position: Box<Account<'info, Position>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenPosition<'info> {
+    pub fn open_position(&mut self, tick_lower: i32, tick_upper: i32, bump: u8) -> Result<()> {
+        self.pool.check_tick(tick_lower)?;
+        self.pool.check_tick(tick_upper)?;
+        require!(tick_lower < tick_upper, AmmError::InvalidTick);
+
+        self.position.set_inner(Position {
+            pool: self.pool.key(),
+            owner: self.owner.key(),
+            tick_lower,
+            tick_upper,
+            liquidity: 0,
+            fee_growth_inside_x: 0,
+            fee_growth_inside_y: 0,
+            fees_owed_x: 0,
+            fees_owed_y: 0,
+            bump,
+        });
+
+        emit!(PositionOpened {
+            pool: self.pool.key(),
+            position: self.position.key(),
+            owner: self.owner.key(),
+            tick_lower,
+            tick_upper,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+use crate::curves;
+use crate::events::Swapped;
+use crate::helpers::extensions::amount_after_fee;
+use crate::helpers::native::{
+    open_temporary_sol_account, unwrap_sol, user_token_account, wrap_sol,
+};
+use crate::state::Config;
+use crate::{assert_no_flash_loan, assert_not_expired, assert_not_locked};
+use crate::{assert_non_zero, errors::AmmError};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+			seeds = [b"mint_lp", config.key().as_ref()],
+			bump = config.lp_bump
+		)]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+			init_if_needed,
+			payer = user,
+			associated_token::mint = mint_x,
+			associated_token::authority = user,
+			associated_token::token_program = token_program_x,
+		)]
+    pub user_ata_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+			init_if_needed,
+			payer = user,
+			associated_token::mint = mint_y,
+			associated_token::authority = user,
+			associated_token::token_program = token_program_y,
+		)]
+    pub user_ata_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: temporary wrapped SOL account, created and closed by the program to pay a native
+    /// side out in lamports. Only needed when a native side's token account is left out
+    #[account(
+			mut,
+			seeds = [b"wsol", user.key().as_ref()],
+			bump
+		)]
+    pub wsol: Option<UncheckedAccount<'info>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_x,
+			associated_token::authority = config,
+			associated_token::token_program = token_program_x,
+		)]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_y,
+			associated_token::authority = config,
+			associated_token::token_program = token_program_y,
+		)]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			has_one = mint_x,
+			has_one = mint_y,
+			seeds = [b"amm", mint_x.key().as_ref(), mint_y.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+			bump = config.bump
+		)]
+    pub config: Box<Account<'info, Config>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = config.token_program_x)]
+    pub token_program_x: Interface<'info, TokenInterface>,
+    #[account(address = config.token_program_y)]
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Swap<'info> {
+    pub fn swap(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        min: u64,
+        expiration: i64,
+        wsol_bump: Option<u8>,
+    ) -> Result<()> {
+        assert_non_zero!([amount]);
+        assert_not_locked!(self.config.locked);
+        assert_no_flash_loan!(self.config.flash_loan_active);
+        assert_not_expired!(expiration);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+        self.config.update_oracle(reserve_x, reserve_y)?;
+
+        let (mint_in, mint_out) = match is_x {
+            true => (self.mint_x.to_account_info(), self.mint_y.to_account_info()),
+            false => (self.mint_y.to_account_info(), self.mint_x.to_account_info()),
+        };
+
+        // price the trade on what the vault receives, the curve charges `config.fee` on top
+        let received = amount_after_fee(&mint_in, amount)?;
+        let res = curves::swap(
+            &self.config,
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            is_x,
+            received,
+            Clock::get()?.unix_timestamp,
+        )?;
+        assert_non_zero!([res.deposit, res.withdraw]);
+
+        // Check for slippage on what the user receives
+        require!(
+            amount_after_fee(&mint_out, res.withdraw)? >= min,
+            AmmError::SlippageExceeded
+        );
+
+        // the protocol's cut of the fee stays in the vault but leaves the LP reserves
+        self.config
+            .accrue_fees(res.fee, is_x, self.mint_lp.supply)?;
+
+        self.deposit_tokens(amount, is_x)?;
+        self.withdraw_tokens(res.withdraw, !is_x, wsol_bump)?;
+
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+        emit!(Swapped {
+            pool: self.config.key(),
+            user: self.user.key(),
+            is_x,
+            amount_in: amount,
+            amount_out: res.withdraw,
+            fee: res.fee,
+            reserve_x,
+            reserve_y,
+            lp_supply: self.mint_lp.supply,
+        });
+        Ok(())
+    }
+
+    pub fn deposit_tokens(&self, amount: u64, is_x: bool) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                &self.user_ata_x,
+                self.vault_x.to_account_info(),
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                &self.user_ata_y,
+                self.vault_y.to_account_info(),
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        // without a token account native SOL is wrapped straight into the vault
+        let Some(from) = user_token_account(from, &mint.key())? else {
+            return wrap_sol(
+                self.user.to_account_info(),
+                to,
+                amount,
+                self.system_program.to_account_info(),
+                token_program,
+            );
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to,
+            mint: mint.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(token_program, accounts);
+
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+
+    pub fn withdraw_tokens(&self, amount: u64, is_x: bool, wsol_bump: Option<u8>) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),
+                &self.user_ata_x,
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.vault_y.to_account_info(),
+                &self.user_ata_y,
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        // without a token account native SOL is paid out through a temporary wrapped SOL account
+        let (to, temporary) = match user_token_account(to, &mint.key())? {
+            Some(to) => (to, false),
+            None => (
+                open_temporary_sol_account(
+                    &self.wsol,
+                    wsol_bump,
+                    mint.to_account_info(),
+                    self.user.to_account_info(),
+                    self.system_program.to_account_info(),
+                    token_program.clone(),
+                )?,
+                true,
+            ),
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to: to.clone(),
+            mint: mint.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+
+        let seed = self.config.seed.to_le_bytes();
+
+        let seeds = &[
+            b"amm",
+            self.mint_x.to_account_info().key.as_ref(),
+            self.mint_y.to_account_info().key.as_ref(),
+            seed.as_ref(),
+            &[self.config.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), accounts, signer_seeds);
+        transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+        if temporary {
+            unwrap_sol(to, self.user.to_account_info(), token_program)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+use crate::curves::concentrated::{
+    fee_growth, sqrt_price_at_tick, swap_step, tick_at_sqrt_price, MAX_TICK, MIN_TICK,
+};
+use crate::events::ConcentratedSwapped;
+use crate::helpers::extensions::{amount_after_fee, amount_before_fee};
+use crate::state::{add_delta, ConcentratedPool, TickArray, TICK_ARRAY_SIZE};
+use crate::{assert_non_zero, assert_not_expired, errors::AmmError};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+// remaining accounts: the tick arrays the price moves through, starting with the one
+// holding the current tick and following the direction of the swap
+#[derive(Accounts)]
+pub struct SwapConcentrated<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+			init_if_needed,
+			payer = user,
+			associated_token::mint = mint_x,
+			associated_token::authority = user,
+			associated_token::token_program = token_program_x,
+		)]
+    pub user_ata_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			init_if_needed,
+			payer = user,
+			associated_token::mint = mint_y,
+			associated_token::authority = user,
+			associated_token::token_program = token_program_y,
+		)]
+    pub user_ata_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_x,
+			associated_token::authority = pool,
+			associated_token::token_program = token_program_x,
+		)]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_y,
+			associated_token::authority = pool,
+			associated_token::token_program = token_program_y,
+		)]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			has_one = mint_x,
+			has_one = mint_y,
+			seeds = [b"clmm", mint_x.key().as_ref(), mint_y.key().as_ref(), pool.seed.to_le_bytes().as_ref()],
+			bump = pool.bump
+		)]
+    pub pool: Box<Account<'info, ConcentratedPool>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = pool.token_program_x)]
+    pub token_program_x: Interface<'info, TokenInterface>,
+    #[account(address = pool.token_program_y)]
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SwapConcentrated<'info> {
+    pub fn swap_concentrated(
+        &mut self,
+        tick_arrays: &'info [AccountInfo<'info>],
+        is_x: bool,
+        amount: u64,
+        min: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        assert_non_zero!([amount]);
+        assert_not_expired!(expiration);
+
+        let mut arrays = tick_arrays
+            .iter()
+            .map(Account::<TickArray>::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        self.check_tick_arrays(&arrays, is_x)?;
+
+        let mint_in = match is_x {
+            true => self.mint_x.to_account_info(),
+            false => self.mint_y.to_account_info(),
+        };
+        let received = amount_after_fee(&mint_in, amount)?;
+        let (remaining, amount_out) = self.swap_through(&mut arrays, is_x, received as u128)?;
+
+        // the swap stops early when it runs out of tick arrays, only charge what it used
+        let amount = match remaining {
+            0 => amount,
+            _ => amount_before_fee(&mint_in, received - remaining)?,
+        };
+        let amount_out = u64::try_from(amount_out).map_err(|_| AmmError::Overflow)?;
+        assert_non_zero!([amount, amount_out]);
+
+        // Check for slippage
+        require!(amount_out >= min, AmmError::SlippageExceeded);
+
+        self.deposit_tokens(amount, is_x)?;
+        self.withdraw_tokens(amount_out, !is_x)?;
+
+        for array in arrays {
+            array.exit(&crate::ID)?;
+        }
+
+        emit!(ConcentratedSwapped {
+            pool: self.pool.key(),
+            user: self.user.key(),
+            is_x,
+            amount_in: amount,
+            amount_out,
+            sqrt_price: self.pool.sqrt_price,
+            tick: self.pool.tick,
+            liquidity: self.pool.liquidity,
+        });
+        Ok(())
+    }
+
+    // move the price through the tick arrays, returns the unspent input and the output
+    fn swap_through(
+        &mut self,
+        arrays: &mut [Account<'info, TickArray>],
+        is_x: bool,
+        amount: u128,
+    ) -> Result<(u64, u128)> {
+        let pool = &mut self.pool;
+        let (mut remaining, mut amount_out) = (amount, 0u128);
+
+        while remaining != 0 {
+            let Some((array, index, tick)) = next_tick(arrays, pool.tick, pool.tick_spacing, is_x)
+            else {
+                break;
+            };
+
+            let target = sqrt_price_at_tick(tick)?;
+            let step = swap_step(
+                is_x,
+                pool.sqrt_price,
+                target,
+                pool.liquidity,
+                remaining,
+                pool.fee,
+            )?;
+            remaining = remaining
+                .checked_sub(step.amount_in + step.fee)
+                .ok_or(AmmError::Underflow)?;
+            amount_out = amount_out
+                .checked_add(step.amount_out)
+                .ok_or(AmmError::Overflow)?;
+
+            // the fee is left in the vault for the liquidity in range
+            if pool.liquidity != 0 {
+                let growth = fee_growth(step.fee, pool.liquidity)?;
+                match is_x {
+                    true => pool.fee_growth_x = pool.fee_growth_x.wrapping_add(growth),
+                    false => pool.fee_growth_y = pool.fee_growth_y.wrapping_add(growth),
+                }
+            }
+            pool.sqrt_price = step.sqrt_price;
+
+            if step.sqrt_price != target {
+                pool.tick = tick_at_sqrt_price(step.sqrt_price)?;
+                continue;
+            }
+
+            let crossed = &mut arrays[array].ticks[index];
+            if crossed.liquidity_gross != 0 {
+                crossed.cross(pool.fee_growth_x, pool.fee_growth_y);
+                let net = match is_x {
+                    true => crossed
+                        .liquidity_net
+                        .checked_neg()
+                        .ok_or(AmmError::Overflow)?,
+                    false => crossed.liquidity_net,
+                };
+                pool.liquidity = add_delta(pool.liquidity, net)?;
+            }
+            pool.tick = match is_x {
+                true => tick - 1,
+                false => tick,
+            };
+        }
+
+        Ok((remaining as u64, amount_out))
+    }
+
+    // the arrays must belong to this pool and be adjacent, in the direction of the swap
+    fn check_tick_arrays(&self, arrays: &[Account<'info, TickArray>], is_x: bool) -> Result<()> {
+        let span = self.pool.tick_array_span();
+        let first = arrays.first().ok_or(AmmError::InvalidTickArray)?;
+        require!(
+            first.start_tick <= self.pool.tick && self.pool.tick < first.start_tick + span,
+            AmmError::InvalidTickArray
+        );
+
+        let step = match is_x {
+            true => -span,
+            false => span,
+        };
+        for (i, array) in arrays.iter().enumerate() {
+            require_keys_eq!(array.pool, self.pool.key(), AmmError::InvalidTickArray);
+            require!(
+                array.start_tick == first.start_tick + step * i as i32,
+                AmmError::InvalidTickArray
+            );
+        }
+        Ok(())
+    }
+
+    pub fn deposit_tokens(&self, amount: u64, is_x: bool) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                self.user_ata_x.to_account_info(),
+                self.vault_x.to_account_info(),
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.user_ata_y.to_account_info(),
+                self.vault_y.to_account_info(),
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to,
+            mint: mint.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(token_program, accounts);
+
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+
+    pub fn withdraw_tokens(&self, amount: u64, is_x: bool) -> Result<()> {
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),
+                self.user_ata_x.to_account_info(),
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.vault_y.to_account_info(),
+                self.user_ata_y.to_account_info(),
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to,
+            mint: mint.to_account_info(),
+            authority: self.pool.to_account_info(),
+        };
+
+        let seed = self.pool.seed.to_le_bytes();
+
+        let seeds = &[
+            b"clmm",
+            self.mint_x.to_account_info().key.as_ref(),
+            self.mint_y.to_account_info().key.as_ref(),
+            seed.as_ref(),
+            &[self.pool.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(token_program, accounts, signer_seeds);
+
+        transfer_checked(cpi_ctx, amount, mint.decimals)
+    }
+}
+
+// next tick the price stops at: the nearest initialized tick in the direction of the swap,
+// or the last tick the arrays cover. Selling X moves down and may stop on the current tick.
+fn next_tick(
+    arrays: &[Account<TickArray>],
+    current: i32,
+    tick_spacing: u16,
+    is_x: bool,
+) -> Option<(usize, usize, i32)> {
+    let mut edge = None;
+    for (a, array) in arrays.iter().enumerate() {
+        for step in 0..TICK_ARRAY_SIZE {
+            let i = match is_x {
+                true => TICK_ARRAY_SIZE - 1 - step,
+                false => step,
+            };
+            let tick = array.start_tick + i as i32 * tick_spacing as i32;
+            let ahead = match is_x {
+                true => tick <= current && tick >= MIN_TICK,
+                false => tick > current && tick <= MAX_TICK,
+            };
+            if !ahead {
+                continue;
+            }
+
+            edge = Some((a, i, tick));
+            if array.ticks[i].liquidity_gross != 0 {
+                return edge;
+            }
+        }
+    }
+    edge
+}
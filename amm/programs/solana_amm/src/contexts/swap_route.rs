@@ -0,0 +1,192 @@
+use crate::curves;
+use crate::events::Swapped;
+use crate::helpers::extensions::amount_after_fee;
+use crate::state::Config;
+use crate::{assert_no_flash_loan, assert_not_expired, assert_not_locked};
+use crate::{assert_non_zero, errors::AmmError};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{transfer_checked, Mint, TokenAccount, TransferChecked},
+};
+
+// remaining accounts per hop:
+// config, mint_lp, mint_in, mint_out, vault_in, vault_out,
+// user_ata_in, user_ata_out, token_program_in, token_program_out
+pub const HOP_ACCOUNTS: usize = 10;
+
+#[derive(Accounts)]
+pub struct SwapRoute<'info> {
+    pub user: Signer<'info>,
+}
+
+impl<'info> SwapRoute<'info> {
+    pub fn swap_route(
+        &self,
+        hops: &'info [AccountInfo<'info>],
+        amount: u64,
+        min_out: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        assert_non_zero!([amount]);
+        assert_not_expired!(expiration);
+        require!(
+            !hops.is_empty() && hops.chunks_exact(HOP_ACCOUNTS).remainder().is_empty(),
+            AmmError::InvalidRoute
+        );
+
+        let mut amount = amount;
+        let mut previous_ata_out: Option<Pubkey> = None;
+
+        for accounts in hops.chunks(HOP_ACCOUNTS) {
+            // each hop spends exactly what the previous one paid out
+            if let Some(ata) = previous_ata_out {
+                require_keys_eq!(ata, accounts[6].key(), AmmError::InvalidRoute);
+            }
+            previous_ata_out = Some(accounts[7].key());
+
+            amount = self.swap_hop(accounts, amount)?;
+        }
+
+        // Check for slippage on what the user ends up with
+        require!(amount >= min_out, AmmError::SlippageExceeded);
+        Ok(())
+    }
+
+    // swap `amount` through one pool and return what reached the user
+    fn swap_hop(&self, accounts: &'info [AccountInfo<'info>], amount: u64) -> Result<u64> {
+        let (config_info, mint_lp) = (&accounts[0], &accounts[1]);
+        let (mint_in, mint_out) = (&accounts[2], &accounts[3]);
+        let (vault_in, vault_out) = (&accounts[4], &accounts[5]);
+        let (user_ata_in, user_ata_out) = (&accounts[6], &accounts[7]);
+        let (token_program_in, token_program_out) = (&accounts[8], &accounts[9]);
+
+        let mut config = Account::<Config>::try_from(config_info)?;
+        let (mint_x, mint_y) = (config.mint_x, config.mint_y);
+        let seed = config.seed.to_le_bytes();
+        let bump = [config.bump];
+        let seeds = &[
+            b"amm",
+            mint_x.as_ref(),
+            mint_y.as_ref(),
+            seed.as_ref(),
+            &bump,
+        ];
+        require_keys_eq!(
+            Pubkey::create_program_address(seeds, &crate::ID)
+                .map_err(|_| AmmError::InvalidRoute)?,
+            config.key(),
+            AmmError::InvalidRoute
+        );
+        assert_not_locked!(config.locked);
+        assert_no_flash_loan!(config.flash_loan_active);
+
+        let is_x = match (mint_in.key(), mint_out.key()) {
+            (a, b) if a == mint_x && b == mint_y => true,
+            (a, b) if a == mint_y && b == mint_x => false,
+            _ => return err!(AmmError::InvalidRoute),
+        };
+        let (program_in, program_out) = match is_x {
+            true => (config.token_program_x, config.token_program_y),
+            false => (config.token_program_y, config.token_program_x),
+        };
+        require_keys_eq!(token_program_in.key(), program_in, AmmError::InvalidRoute);
+        require_keys_eq!(token_program_out.key(), program_out, AmmError::InvalidRoute);
+        require_keys_eq!(
+            vault_in.key(),
+            get_associated_token_address_with_program_id(
+                &config.key(),
+                &mint_in.key(),
+                &program_in
+            ),
+            AmmError::InvalidRoute
+        );
+        require_keys_eq!(
+            vault_out.key(),
+            get_associated_token_address_with_program_id(
+                &config.key(),
+                &mint_out.key(),
+                &program_out
+            ),
+            AmmError::InvalidRoute
+        );
+        require_keys_eq!(
+            mint_lp.key(),
+            Pubkey::create_program_address(
+                &[b"mint_lp", config.key().as_ref(), &[config.lp_bump]],
+                &crate::ID
+            )
+            .map_err(|_| AmmError::InvalidRoute)?,
+            AmmError::InvalidRoute
+        );
+
+        let supply = InterfaceAccount::<Mint>::try_from(mint_lp)?.supply;
+        let mint_in_account = InterfaceAccount::<Mint>::try_from(mint_in)?;
+        let mint_out_account = InterfaceAccount::<Mint>::try_from(mint_out)?;
+        let vault_in_amount = InterfaceAccount::<TokenAccount>::try_from(vault_in)?.amount;
+        let vault_out_amount = InterfaceAccount::<TokenAccount>::try_from(vault_out)?.amount;
+
+        let (reserve_x, reserve_y) = match is_x {
+            true => config.reserves(vault_in_amount, vault_out_amount)?,
+            false => config.reserves(vault_out_amount, vault_in_amount)?,
+        };
+        config.update_oracle(reserve_x, reserve_y)?;
+
+        // same pricing as a single pool swap
+        let received = amount_after_fee(mint_in, amount)?;
+        let res = curves::swap(
+            &config,
+            reserve_x,
+            reserve_y,
+            supply,
+            is_x,
+            received,
+            Clock::get()?.unix_timestamp,
+        )?;
+        assert_non_zero!([res.deposit, res.withdraw]);
+        config.accrue_fees(res.fee, is_x, supply)?;
+
+        let accounts = TransferChecked {
+            from: user_ata_in.clone(),
+            mint: mint_in.clone(),
+            to: vault_in.clone(),
+            authority: self.user.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(token_program_in.clone(), accounts);
+        transfer_checked(cpi_ctx, amount, mint_in_account.decimals)?;
+
+        let accounts = TransferChecked {
+            from: vault_out.clone(),
+            mint: mint_out.clone(),
+            to: user_ata_out.clone(),
+            authority: config_info.clone(),
+        };
+        let signer_seeds = &[&seeds[..]];
+        let cpi_ctx =
+            CpiContext::new_with_signer(token_program_out.clone(), accounts, signer_seeds);
+        transfer_checked(cpi_ctx, res.withdraw, mint_out_account.decimals)?;
+
+        let vault_in_amount = InterfaceAccount::<TokenAccount>::try_from(vault_in)?.amount;
+        let vault_out_amount = InterfaceAccount::<TokenAccount>::try_from(vault_out)?.amount;
+        let (reserve_x, reserve_y) = match is_x {
+            true => config.reserves(vault_in_amount, vault_out_amount)?,
+            false => config.reserves(vault_out_amount, vault_in_amount)?,
+        };
+        emit!(Swapped {
+            pool: config.key(),
+            user: self.user.key(),
+            is_x,
+            amount_in: amount,
+            amount_out: res.withdraw,
+            fee: res.fee,
+            reserve_x,
+            reserve_y,
+            lp_supply: supply,
+        });
+
+        // persist the oracle and protocol fee updates before a later hop reloads this pool
+        config.exit(&crate::ID)?;
+
+        amount_after_fee(mint_out, res.withdraw)
+    }
+}
@@ -0,0 +1,175 @@
+use crate::curves::stable_swap::{MAX_AMP, MAX_AMP_CHANGE, MIN_AMP, MIN_RAMP_DURATION};
+use crate::events::{AmpRampUpdated, AuthorityUpdated, FeeUpdated, PoolLocked, TreasuryUpdated};
+use crate::has_update_authority;
+use crate::{
+    errors::AmmError,
+    state::{Config, CurveType},
+};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct Update<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+			mut,
+			seeds = [b"amm", config.mint_x.as_ref(), config.mint_y.as_ref(), config.seed.to_le_bytes().as_ref()],
+			bump = config.bump
+		)]
+    pub config: Box<Account<'info, Config>>,
+}
+
+impl<'info> Update<'info> {
+    pub fn lock(&mut self) -> Result<()> {
+        has_update_authority!(self);
+        self.config.locked = true;
+        emit!(PoolLocked {
+            pool: self.config.key(),
+            authority: self.user.key(),
+            locked: true,
+        });
+        Ok(())
+    }
+
+    pub fn unlock(&mut self) -> Result<()> {
+        has_update_authority!(self);
+        self.config.locked = false;
+        emit!(PoolLocked {
+            pool: self.config.key(),
+            authority: self.user.key(),
+            locked: false,
+        });
+        Ok(())
+    }
+
+    pub fn update_fee(&mut self, fee: u16) -> Result<()> {
+        has_update_authority!(self);
+        require!(fee < 10_000, AmmError::InvalidFee);
+        self.config.fee = fee;
+        self.emit_fee_updated();
+        Ok(())
+    }
+
+    pub fn update_flash_fee(&mut self, flash_fee: u16) -> Result<()> {
+        has_update_authority!(self);
+        require!(flash_fee < 10_000, AmmError::InvalidFee);
+        self.config.flash_fee = flash_fee;
+        self.emit_fee_updated();
+        Ok(())
+    }
+
+    pub fn update_protocol_fee(&mut self, protocol_fee: u16) -> Result<()> {
+        has_update_authority!(self);
+        require!(protocol_fee < 10_000, AmmError::InvalidFee);
+        self.config.protocol_fee = protocol_fee;
+        self.emit_fee_updated();
+        Ok(())
+    }
+
+    pub fn update_treasury(&mut self, treasury: Pubkey) -> Result<()> {
+        has_update_authority!(self);
+        self.config.treasury = treasury;
+        emit!(TreasuryUpdated {
+            pool: self.config.key(),
+            authority: self.user.key(),
+            treasury,
+        });
+        Ok(())
+    }
+
+    pub fn ramp_amp(&mut self, target_amp: u64, ramp_end: i64) -> Result<()> {
+        has_update_authority!(self);
+        require!(
+            self.config.curve == CurveType::StableSwap,
+            AmmError::UnsupportedCurve
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let current = self.config.amp_at(now);
+        require!(
+            (MIN_AMP..=MAX_AMP).contains(&target_amp)
+                && target_amp <= current.saturating_mul(MAX_AMP_CHANGE)
+                && current <= target_amp.saturating_mul(MAX_AMP_CHANGE)
+                && ramp_end >= now.saturating_add(MIN_RAMP_DURATION),
+            AmmError::InvalidAmplification
+        );
+
+        self.config.amp_initial = current;
+        self.config.amp_target = target_amp;
+        self.config.amp_ramp_start = now;
+        self.config.amp_ramp_end = ramp_end;
+        self.emit_amp_ramp_updated();
+        Ok(())
+    }
+
+    pub fn stop_ramp_amp(&mut self) -> Result<()> {
+        has_update_authority!(self);
+        require!(
+            self.config.curve == CurveType::StableSwap,
+            AmmError::UnsupportedCurve
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let current = self.config.amp_at(now);
+        self.config.amp_initial = current;
+        self.config.amp_target = current;
+        self.config.amp_ramp_start = now;
+        self.config.amp_ramp_end = now;
+        self.emit_amp_ramp_updated();
+        Ok(())
+    }
+
+    pub fn propose_authority(&mut self, new_authority: Option<Pubkey>) -> Result<()> {
+        has_update_authority!(self);
+        match new_authority {
+            Some(_) => self.config.pending_authority = new_authority,
+            // renouncing is one-way, there is nobody left to accept it
+            None => {
+                self.config.authority = None;
+                self.config.pending_authority = None;
+            }
+        }
+        self.emit_authority_updated();
+        Ok(())
+    }
+
+    pub fn accept_authority(&mut self) -> Result<()> {
+        match self.config.pending_authority {
+            Some(a) => {
+                require_keys_eq!(a, self.user.key(), AmmError::InvalidAuthority);
+            }
+            None => return err!(AmmError::NoAuthoritySet),
+        }
+        self.config.authority = self.config.pending_authority.take();
+        self.emit_authority_updated();
+        Ok(())
+    }
+
+    fn emit_fee_updated(&self) {
+        emit!(FeeUpdated {
+            pool: self.config.key(),
+            authority: self.user.key(),
+            fee: self.config.fee,
+            protocol_fee: self.config.protocol_fee,
+            flash_fee: self.config.flash_fee,
+        });
+    }
+
+    fn emit_amp_ramp_updated(&self) {
+        emit!(AmpRampUpdated {
+            pool: self.config.key(),
+            amp_initial: self.config.amp_initial,
+            amp_target: self.config.amp_target,
+            ramp_start: self.config.amp_ramp_start,
+            ramp_end: self.config.amp_ramp_end,
+        });
+    }
+
+    fn emit_authority_updated(&self) {
+        emit!(AuthorityUpdated {
+            pool: self.config.key(),
+            authority: self.config.authority,
+            pending_authority: self.config.pending_authority,
+        });
+    }
+}
@@ -0,0 +1,321 @@
+use crate::curves;
+use crate::events::LiquidityRemoved;
+use crate::helpers::extensions::amount_after_fee;
+use crate::helpers::native::{open_temporary_sol_account, unwrap_sol, user_token_account};
+use crate::state::{Config, LpPosition};
+use crate::{assert_no_flash_loan, assert_not_expired, assert_not_locked};
+use crate::{assert_non_zero, errors::AmmError};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        burn, transfer_checked, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+			mut,
+			seeds = [b"mint_lp", config.key().as_ref()],
+			bump = config.lp_bump
+		)]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+			init_if_needed,
+			payer = user,
+			associated_token::mint = mint_x,
+			associated_token::authority = user,
+			associated_token::token_program = token_program_x,
+		)]
+    pub user_ata_x: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+			init_if_needed,
+			payer = user,
+			associated_token::mint = mint_y,
+			associated_token::authority = user,
+			associated_token::token_program = token_program_y,
+		)]
+    pub user_ata_y: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: temporary wrapped SOL account, created and closed by the program to pay a native
+    /// side out in lamports. Only needed when a native side's token account is left out
+    #[account(
+			mut,
+			seeds = [b"wsol", user.key().as_ref()],
+			bump
+		)]
+    pub wsol: Option<UncheckedAccount<'info>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_lp,
+			associated_token::authority = user,
+			associated_token::token_program = token_program,
+		)]
+    pub user_ata_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_x,
+			associated_token::authority = config,
+			associated_token::token_program = token_program_x,
+		)]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			associated_token::mint = mint_y,
+			associated_token::authority = config,
+			associated_token::token_program = token_program_y,
+		)]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+			mut,
+			has_one = mint_x,
+			has_one = mint_y,
+			seeds = [b"amm", mint_x.key().as_ref(), mint_y.key().as_ref(), config.seed.to_le_bytes().as_ref()],
+			bump = config.bump
+		)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(
+			mut,
+			seeds = [b"lp_position", config.key().as_ref(), user.key().as_ref()],
+			bump = position.bump
+		)]
+    pub position: Option<Box<Account<'info, LpPosition>>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    #[account(address = config.token_program_x)]
+    pub token_program_x: Interface<'info, TokenInterface>,
+    #[account(address = config.token_program_y)]
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Withdraw<'info> {
+    pub fn withdraw(
+        &mut self,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64,
+        wsol_bump: Option<u8>,
+    ) -> Result<()> {
+        assert_non_zero!([amount]);
+        assert_not_locked!(self.config.locked);
+        assert_no_flash_loan!(self.config.flash_loan_active);
+        assert_not_expired!(expiration);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+        self.config.update_oracle(reserve_x, reserve_y)?;
+        require!(
+            self.mint_lp.supply != 0 && reserve_x != 0 && reserve_y != 0,
+            AmmError::NoLiquidityInPool
+        );
+        require!(
+            amount <= self.user_ata_lp.amount,
+            AmmError::InsufficientBalance
+        );
+
+        let (x, y) = curves::withdraw_amounts(reserve_x, reserve_y, self.mint_lp.supply, amount)?;
+
+        // Check for slippage on what the user receives
+        require!(
+            amount_after_fee(&self.mint_x.to_account_info(), x)? >= min_x
+                && amount_after_fee(&self.mint_y.to_account_info(), y)? >= min_y,
+            AmmError::SlippageExceeded
+        );
+        self.track_position(amount)?;
+        self.burn_lp_token(amount)?;
+        self.withdraw_tokens(x, true, wsol_bump)?;
+        self.withdraw_tokens(y, false, wsol_bump)?;
+        self.emit_liquidity_removed(x, y, amount)
+    }
+
+    pub fn withdraw_single(
+        &mut self,
+        is_x: bool,
+        amount: u64,
+        min_out: u64,
+        expiration: i64,
+        wsol_bump: Option<u8>,
+    ) -> Result<()> {
+        assert_non_zero!([amount]);
+        assert_not_locked!(self.config.locked);
+        assert_no_flash_loan!(self.config.flash_loan_active);
+        assert_not_expired!(expiration);
+
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+        self.config.update_oracle(reserve_x, reserve_y)?;
+        require!(
+            self.mint_lp.supply != 0 && reserve_x != 0 && reserve_y != 0,
+            AmmError::NoLiquidityInPool
+        );
+        require!(
+            amount <= self.user_ata_lp.amount,
+            AmmError::InsufficientBalance
+        );
+
+        // the other side's share never leaves the vault, it is swapped on paper
+        let quote = curves::zap_out(
+            &self.config,
+            reserve_x,
+            reserve_y,
+            self.mint_lp.supply,
+            is_x,
+            amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let mint_out = match is_x {
+            true => self.mint_x.to_account_info(),
+            false => self.mint_y.to_account_info(),
+        };
+
+        // Check for slippage on what the user receives
+        require!(
+            amount_after_fee(&mint_out, quote.amount_out)? >= min_out,
+            AmmError::SlippageExceeded
+        );
+        // settle the position first, the burnt tokens don't share the fee of the internal swap
+        self.track_position(amount)?;
+        self.config
+            .accrue_fees(quote.swap.fee, !is_x, self.mint_lp.supply - amount)?;
+
+        self.burn_lp_token(amount)?;
+        self.withdraw_tokens(quote.amount_out, is_x, wsol_bump)?;
+
+        let (x, y) = match is_x {
+            true => (quote.amount_out, 0),
+            false => (0, quote.amount_out),
+        };
+        self.emit_liquidity_removed(x, y, amount)
+    }
+
+    // read back the vaults and the LP supply once the transfers are done
+    pub fn emit_liquidity_removed(
+        &mut self,
+        amount_x: u64,
+        amount_y: u64,
+        lp_amount: u64,
+    ) -> Result<()> {
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.mint_lp.reload()?;
+        let (reserve_x, reserve_y) = self
+            .config
+            .reserves(self.vault_x.amount, self.vault_y.amount)?;
+
+        emit!(LiquidityRemoved {
+            pool: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount,
+            reserve_x,
+            reserve_y,
+            lp_supply: self.mint_lp.supply,
+        });
+        Ok(())
+    }
+
+    // remove `burnt` LP tokens from the user's position record, when one was passed
+    pub fn track_position(&mut self, burnt: u64) -> Result<()> {
+        let Some(position) = self.position.as_mut() else {
+            return Ok(());
+        };
+
+        position.checkpoint(&self.config)?;
+        // LP tokens obtained elsewhere were never on the record
+        position.liquidity = position.liquidity.saturating_sub(burnt);
+        Ok(())
+    }
+
+    pub fn withdraw_tokens(&self, amount: u64, is_x: bool, wsol_bump: Option<u8>) -> Result<()> {
+        assert_non_zero!([amount]);
+
+        let (from, to, mint, token_program) = match is_x {
+            true => (
+                self.vault_x.to_account_info(),
+                &self.user_ata_x,
+                self.mint_x.clone(),
+                self.token_program_x.to_account_info(),
+            ),
+            false => (
+                self.vault_y.to_account_info(),
+                &self.user_ata_y,
+                self.mint_y.clone(),
+                self.token_program_y.to_account_info(),
+            ),
+        };
+
+        // without a token account native SOL is paid out through a temporary wrapped SOL account
+        let (to, temporary) = match user_token_account(to, &mint.key())? {
+            Some(to) => (to, false),
+            None => (
+                open_temporary_sol_account(
+                    &self.wsol,
+                    wsol_bump,
+                    mint.to_account_info(),
+                    self.user.to_account_info(),
+                    self.system_program.to_account_info(),
+                    token_program.clone(),
+                )?,
+                true,
+            ),
+        };
+
+        let accounts = TransferChecked {
+            from,
+            to: to.clone(),
+            mint: mint.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+
+        let seed = self.config.seed.to_le_bytes();
+
+        let seeds = &[
+            b"amm",
+            self.mint_x.to_account_info().key.as_ref(),
+            self.mint_y.to_account_info().key.as_ref(),
+            seed.as_ref(),
+            &[self.config.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), accounts, signer_seeds);
+        transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+        if temporary {
+            unwrap_sol(to, self.user.to_account_info(), token_program)?;
+        }
+        Ok(())
+    }
+
+    pub fn burn_lp_token(&self, amount: u64) -> Result<()> {
+        let accounts = Burn {
+            mint: self.mint_lp.to_account_info(),
+            from: self.user_ata_lp.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), accounts);
+
+        burn(cpi_ctx, amount)
+    }
+}
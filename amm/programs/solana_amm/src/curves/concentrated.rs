@@ -0,0 +1,316 @@
+use crate::errors::AmmError;
+use anchor_lang::prelude::*;
+
+// concentrated liquidity math, prices are Q64.64 square roots of the price of X in Y
+// and tick `t` sits at price 1.0001^t
+
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+pub const MAX_TICK_SPACING: u16 = 16_384;
+
+const Q64: u128 = 1 << 64;
+
+// sqrt(1.0001)^(2^i), Q64.64
+const SQRT_POWERS: [u128; 19] = [
+    18447666387855959850,
+    18448588748116922571,
+    18450433606991734263,
+    18454123878217468680,
+    18461506635090006701,
+    18476281010653910144,
+    18505865242158250041,
+    18565175891880433522,
+    18684368066214940582,
+    18925053041275764671,
+    19415764168677886926,
+    20435687552633177494,
+    22639080592224303007,
+    27784196929998399742,
+    41848122137994986128,
+    94936283578220370716,
+    488590176327622479860,
+    12941056668319229769860,
+    9078618265828848800676189,
+];
+
+pub fn min_sqrt_price() -> u128 {
+    sqrt_price_at_tick(MIN_TICK).unwrap_or_default()
+}
+
+pub fn max_sqrt_price() -> u128 {
+    sqrt_price_at_tick(MAX_TICK).unwrap_or_default()
+}
+
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    require!((MIN_TICK..=MAX_TICK).contains(&tick), AmmError::InvalidTick);
+
+    let abs = tick.unsigned_abs();
+    let mut price = Q64;
+    for (i, power) in SQRT_POWERS.iter().enumerate() {
+        if abs & (1 << i) != 0 {
+            price = mul_shift_64(price, *power)?;
+        }
+    }
+
+    // 1.0001^-t = 1 / 1.0001^t
+    match tick < 0 {
+        true => Ok(u128::MAX / price),
+        false => Ok(price),
+    }
+}
+
+// greatest tick whose price is at or below `sqrt_price`
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Result<i32> {
+    require!(
+        sqrt_price >= min_sqrt_price() && sqrt_price <= max_sqrt_price(),
+        AmmError::InvalidPrice
+    );
+
+    let (mut lo, mut hi) = (MIN_TICK, MAX_TICK);
+    while lo < hi {
+        let mid = lo + (hi - lo + 1) / 2;
+        match sqrt_price_at_tick(mid)? <= sqrt_price {
+            true => lo = mid,
+            false => hi = mid - 1,
+        }
+    }
+    Ok(lo)
+}
+
+// amount of X between two prices for `liquidity`, L * (sb - sa) / (sa * sb)
+pub fn amount_x_delta(sqrt_a: u128, sqrt_b: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (sqrt_a, sqrt_b) = (sqrt_a.min(sqrt_b), sqrt_a.max(sqrt_b));
+    require!(sqrt_a != 0, AmmError::InvalidPrice);
+
+    let amount = mul_div(liquidity, sqrt_b - sqrt_a, sqrt_b, round_up)?;
+    mul_div(amount, Q64, sqrt_a, round_up)
+}
+
+// amount of Y between two prices for `liquidity`, L * (sb - sa)
+pub fn amount_y_delta(sqrt_a: u128, sqrt_b: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (sqrt_a, sqrt_b) = (sqrt_a.min(sqrt_b), sqrt_a.max(sqrt_b));
+    mul_div(liquidity, sqrt_b - sqrt_a, Q64, round_up)
+}
+
+// token amounts backing `liquidity` in [sqrt_lower, sqrt_upper] at the current price
+pub fn amounts_for_liquidity(
+    sqrt_price: u128,
+    sqrt_lower: u128,
+    sqrt_upper: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<(u64, u64)> {
+    let sqrt_price = sqrt_price.clamp(sqrt_lower, sqrt_upper);
+    let x = amount_x_delta(sqrt_price, sqrt_upper, liquidity, round_up)?;
+    let y = amount_y_delta(sqrt_lower, sqrt_price, liquidity, round_up)?;
+    Ok((
+        u64::try_from(x).map_err(|_| AmmError::Overflow)?,
+        u64::try_from(y).map_err(|_| AmmError::Overflow)?,
+    ))
+}
+
+pub struct SwapStep {
+    pub sqrt_price: u128, // price after the step
+    pub amount_in: u128,  // input spent on the curve, without the fee
+    pub amount_out: u128,
+    pub fee: u128, // input charged as fee
+}
+
+// swap up to `amount` of input within a single liquidity range, stopping at `sqrt_target`,
+// selling X moves the price down
+pub fn swap_step(
+    is_x: bool,
+    sqrt_price: u128,
+    sqrt_target: u128,
+    liquidity: u128,
+    amount: u128,
+    fee: u16,
+) -> Result<SwapStep> {
+    let fee = fee as u128;
+    let amount_less_fee = mul_div(amount, 10_000 - fee, 10_000, false)?;
+
+    let amount_in_max = match is_x {
+        true => amount_x_delta(sqrt_target, sqrt_price, liquidity, true)?,
+        false => amount_y_delta(sqrt_price, sqrt_target, liquidity, true)?,
+    };
+
+    let next = match amount_less_fee >= amount_in_max {
+        true => sqrt_target,
+        false => match is_x {
+            true => next_sqrt_price_from_x(sqrt_price, liquidity, amount_less_fee)?,
+            false => next_sqrt_price_from_y(sqrt_price, liquidity, amount_less_fee)?,
+        },
+    };
+
+    let (amount_in, amount_out) = match is_x {
+        true => (
+            amount_x_delta(next, sqrt_price, liquidity, true)?,
+            amount_y_delta(next, sqrt_price, liquidity, false)?,
+        ),
+        false => (
+            amount_y_delta(sqrt_price, next, liquidity, true)?,
+            amount_x_delta(sqrt_price, next, liquidity, false)?,
+        ),
+    };
+
+    // the whole rest is the fee when the step ends inside the range
+    let fee = match next == sqrt_target {
+        true => mul_div(amount_in, fee, 10_000 - fee, true)?,
+        false => amount.checked_sub(amount_in).ok_or(AmmError::Underflow)?,
+    };
+
+    Ok(SwapStep {
+        sqrt_price: next,
+        amount_in,
+        amount_out,
+        fee,
+    })
+}
+
+// Q64.64 fee growth per unit of liquidity for `fee` earned by `liquidity`
+pub fn fee_growth(fee: u128, liquidity: u128) -> Result<u128> {
+    mul_div(fee, Q64, liquidity, false)
+}
+
+// price after adding `amount` of X, L * sqrt / (L + amount * sqrt), rounded up
+fn next_sqrt_price_from_x(sqrt_price: u128, liquidity: u128, amount: u128) -> Result<u128> {
+    let denominator = liquidity
+        .checked_add(mul_div(amount, sqrt_price, Q64, false)?)
+        .ok_or(AmmError::Overflow)?;
+    mul_div(liquidity, sqrt_price, denominator, true)
+}
+
+// price after adding `amount` of Y, sqrt + amount / L, rounded down
+fn next_sqrt_price_from_y(sqrt_price: u128, liquidity: u128, amount: u128) -> Result<u128> {
+    sqrt_price
+        .checked_add(mul_div(amount, Q64, liquidity, false)?)
+        .ok_or(AmmError::Overflow.into())
+}
+
+// a * b >> 64
+pub fn mul_shift_64(a: u128, b: u128) -> Result<u128> {
+    let (hi, lo) = full_mul(a, b);
+    require!(hi < Q64, AmmError::Overflow);
+    Ok(hi << 64 | lo >> 64)
+}
+
+// a * b / d with a 256 bit intermediate product
+pub fn mul_div(a: u128, b: u128, d: u128, round_up: bool) -> Result<u128> {
+    require!(d != 0, AmmError::Overflow);
+
+    let (hi, lo) = full_mul(a, b);
+    let (quotient, remainder) = match hi {
+        0 => (lo / d, lo % d),
+        _ => {
+            require!(hi < d, AmmError::Overflow);
+            // long division of hi:lo by d, one bit at a time
+            let (mut quotient, mut remainder) = (0u128, hi);
+            for i in (0..128).rev() {
+                let carry = remainder >> 127;
+                remainder = remainder << 1 | (lo >> i & 1);
+                quotient <<= 1;
+                if carry == 1 || remainder >= d {
+                    remainder = remainder.wrapping_sub(d);
+                    quotient |= 1;
+                }
+            }
+            (quotient, remainder)
+        }
+    };
+
+    match round_up && remainder != 0 {
+        true => quotient.checked_add(1).ok_or(AmmError::Overflow.into()),
+        false => Ok(quotient),
+    }
+}
+
+// 256 bit product of two u128 as (high, low)
+fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    let mask = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & mask);
+    let (b_hi, b_lo) = (b >> 64, b & mask);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    let middle = (lo_lo >> 64) + (hi_lo & mask) + (lo_hi & mask);
+    let lo = (middle << 64) | (lo_lo & mask);
+    let hi = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+    (hi, lo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_round_trip_through_prices() {
+        assert_eq!(sqrt_price_at_tick(0).unwrap(), Q64);
+        for tick in [MIN_TICK, MIN_TICK + 1, -1, 0, 1, MAX_TICK - 1, MAX_TICK] {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+        }
+        // just below a tick's price is the tick before it
+        for tick in [-1, 0, 1, MAX_TICK] {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(sqrt_price - 1).unwrap(), tick - 1);
+        }
+        assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+        assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+        assert!(tick_at_sqrt_price(min_sqrt_price() - 1).is_err());
+        assert!(tick_at_sqrt_price(max_sqrt_price() + 1).is_err());
+    }
+
+    #[test]
+    fn mul_div_rounds_and_checks_overflow() {
+        assert_eq!(mul_div(7, 3, 2, false).unwrap(), 10);
+        assert_eq!(mul_div(7, 3, 2, true).unwrap(), 11);
+        assert_eq!(mul_div(6, 3, 2, true).unwrap(), 9);
+
+        // products past 128 bits
+        assert_eq!(
+            mul_div(1 << 100, 1 << 100, 1 << 80, true).unwrap(),
+            1 << 120
+        );
+        assert_eq!(
+            mul_div(u128::MAX, u128::MAX, u128::MAX, false).unwrap(),
+            u128::MAX
+        );
+        assert_eq!(mul_div(u128::MAX, 3, 4, false).unwrap(), (3 << 126) - 1);
+        assert_eq!(mul_div(u128::MAX, 3, 4, true).unwrap(), 3 << 126);
+
+        // quotients that don't fit, or no divisor
+        assert!(mul_div(1 << 100, 1 << 100, 1 << 72, false).is_err());
+        assert!(mul_div(u128::MAX, 3, 2, false).is_err());
+        assert!(mul_div(u128::MAX, u128::MAX, u128::MAX, true).is_ok());
+        assert!(mul_div(1, 1, 0, false).is_err());
+
+        assert_eq!(mul_shift_64(3 << 64, 5 << 64).unwrap(), 15 << 64);
+        assert!(mul_shift_64(u128::MAX, u128::MAX).is_err());
+    }
+
+    #[test]
+    fn swap_step_spends_the_whole_input_inside_the_range() {
+        // 10_000 Y at price 1 with 1M liquidity and a 0.3% fee: 9_970 moves the price to
+        // 1.00997, which pays out 1M * 0.00997 / 1.00997 = 9_871.58 X
+        let target = sqrt_price_at_tick(1_000).unwrap();
+        let step = swap_step(false, Q64, target, 1_000_000, 10_000, 30).unwrap();
+        assert_eq!(step.sqrt_price, Q64 + 9_970 * Q64 / 1_000_000);
+        assert_eq!(step.amount_in, 9_970);
+        assert_eq!(step.amount_out, 9_871);
+        assert_eq!(step.fee, 30);
+    }
+
+    #[test]
+    fn swap_step_stops_at_the_target() {
+        // selling X from price 1 to 0.25 with 1M liquidity takes exactly 1M * (2 - 1) X and
+        // pays out 1M * (1 - 0.5) Y, the fee is 0.3% of the gross input
+        let step = swap_step(true, Q64, Q64 / 2, 1_000_000, 2_000_000, 30).unwrap();
+        assert_eq!(step.sqrt_price, Q64 / 2);
+        assert_eq!(step.amount_in, 1_000_000);
+        assert_eq!(step.amount_out, 500_000);
+        assert_eq!(step.fee, 3_010); // ceil(1_000_000 * 30 / 9_970)
+    }
+}
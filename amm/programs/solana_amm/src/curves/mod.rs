@@ -0,0 +1,212 @@
+use crate::errors::AmmError;
+use crate::state::{Config, CurveType};
+use anchor_lang::prelude::*;
+use constant_product_curve::{ConstantProduct, LiquidityPair};
+
+pub mod concentrated;
+pub mod stable_swap;
+
+// LP tokens minted to the pool itself on the first deposit and never redeemable,
+// so the supply can never return to zero and a share can't be inflated
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+pub struct SwapQuote {
+    pub deposit: u64,  // amount of the input token entering the reserves
+    pub withdraw: u64, // amount of the output token leaving the reserves
+    pub fee: u64,      // part of `deposit` charged as fee
+}
+
+// price a swap of `amount` against the reserves with the pool's curve
+pub fn swap(
+    config: &Config,
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    is_x: bool,
+    amount: u64,
+    now: i64,
+) -> Result<SwapQuote> {
+    match config.curve {
+        CurveType::ConstantProduct => {
+            let mut curve = ConstantProduct::init(reserve_x, reserve_y, supply, config.fee, None)
+                .map_err(AmmError::from)?;
+
+            let pair = match is_x {
+                true => LiquidityPair::X,
+                false => LiquidityPair::Y,
+            };
+
+            let res = curve.swap(pair, amount, 0).map_err(AmmError::from)?;
+            Ok(SwapQuote {
+                deposit: res.deposit,
+                withdraw: res.withdraw,
+                fee: res.fee,
+            })
+        }
+        CurveType::StableSwap => {
+            let (reserve_in, reserve_out) = match is_x {
+                true => (reserve_x, reserve_y),
+                false => (reserve_y, reserve_x),
+            };
+
+            let (withdraw, fee) = stable_swap::swap(
+                config.amp_at(now),
+                reserve_in,
+                reserve_out,
+                amount,
+                config.fee,
+            )?;
+            Ok(SwapQuote {
+                deposit: amount,
+                withdraw,
+                fee,
+            })
+        }
+    }
+}
+
+// amounts of X and Y to deposit to mint `liquidity` LP tokens, rounded up
+// so the new tokens are never worth more than what was paid for them
+pub fn deposit_amounts(
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    liquidity: u64,
+) -> Result<(u64, u64)> {
+    require!(supply != 0, AmmError::NoLiquidityInPool);
+    let amount = |reserve: u64| {
+        let amount = (reserve as u128 * liquidity as u128).div_ceil(supply as u128);
+        u64::try_from(amount).map_err(|_| AmmError::Overflow)
+    };
+    Ok((amount(reserve_x)?, amount(reserve_y)?))
+}
+
+// amounts of X and Y paid out for burning `liquidity` LP tokens, rounded down
+// so the burnt tokens never take out more than their share
+pub fn withdraw_amounts(
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    liquidity: u64,
+) -> Result<(u64, u64)> {
+    require!(supply != 0, AmmError::NoLiquidityInPool);
+    require!(liquidity <= supply, AmmError::InsufficientBalance);
+    let amount = |reserve: u64| (reserve as u128 * liquidity as u128 / supply as u128) as u64;
+    Ok((amount(reserve_x), amount(reserve_y)))
+}
+
+pub struct ZapInQuote {
+    pub swap: SwapQuote, // internal swap of part of the deposit into the other side
+    pub liquidity: u64,  // LP tokens minted for the whole deposit
+}
+
+pub struct ZapOutQuote {
+    pub amount_out: u64, // total of the requested token paid out
+    pub swap: SwapQuote, // internal swap of the other side into the requested token
+}
+
+// single-sided deposit of `amount`: swap just enough through the curve that the rest
+// and the swap output match the pool ratio, then add both as liquidity
+pub fn zap_in(
+    config: &Config,
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    is_x: bool,
+    amount: u64,
+    now: i64,
+) -> Result<ZapInQuote> {
+    let (reserve_in, reserve_out) = match is_x {
+        true => (reserve_x as u128, reserve_y as u128),
+        false => (reserve_y as u128, reserve_x as u128),
+    };
+
+    // the remainder left after swapping `s` shrinks as `s` grows while the output grows,
+    // bisect for the point where remainder / output matches the reserves after the swap
+    let (mut lo, mut hi) = (0u64, amount);
+    let tolerance = amount / 10_000;
+    while hi - lo > tolerance.max(1) {
+        let mid = lo + (hi - lo) / 2;
+        let quote = swap(config, reserve_x, reserve_y, supply, is_x, mid, now)?;
+        let rest = (amount - mid) as u128;
+        let lhs = rest
+            .checked_mul(reserve_out - quote.withdraw as u128)
+            .ok_or(AmmError::Overflow)?;
+        let rhs = (quote.withdraw as u128)
+            .checked_mul(reserve_in + mid as u128)
+            .ok_or(AmmError::Overflow)?;
+        match lhs > rhs {
+            true => lo = mid,
+            false => hi = mid,
+        }
+    }
+
+    let swap_amount = hi;
+    let quote = swap(config, reserve_x, reserve_y, supply, is_x, swap_amount, now)?;
+    require!(quote.withdraw != 0, AmmError::InvalidAmount);
+
+    // reserves after the internal swap, without the protocol's cut of its fee
+    let reserve_in =
+        reserve_in + swap_amount as u128 - config.protocol_fee_amount(quote.fee)? as u128;
+    let reserve_out = reserve_out - quote.withdraw as u128;
+
+    let liquidity = ((amount - swap_amount) as u128 * supply as u128 / reserve_in)
+        .min(quote.withdraw as u128 * supply as u128 / reserve_out);
+
+    Ok(ZapInQuote {
+        swap: quote,
+        liquidity: u64::try_from(liquidity).map_err(|_| AmmError::Overflow)?,
+    })
+}
+
+// single-sided withdrawal of `liquidity`: withdraw both sides proportionally,
+// then swap the other side through the curve into the requested token
+pub fn zap_out(
+    config: &Config,
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    is_x: bool,
+    liquidity: u64,
+    now: i64,
+) -> Result<ZapOutQuote> {
+    let (x, y) = withdraw_amounts(reserve_x, reserve_y, supply, liquidity)?;
+
+    let (reserve_x, reserve_y, supply) = (reserve_x - x, reserve_y - y, supply - liquidity);
+    let (direct, other) = match is_x {
+        true => (x, y),
+        false => (y, x),
+    };
+
+    let quote = swap(config, reserve_x, reserve_y, supply, !is_x, other, now)?;
+    Ok(ZapOutQuote {
+        amount_out: direct
+            .checked_add(quote.withdraw)
+            .ok_or(AmmError::Overflow)?,
+        swap: quote,
+    })
+}
+
+// LP supply for the first deposit, the geometric mean of the two amounts
+pub fn initial_liquidity(amount_x: u64, amount_y: u64) -> Result<u64> {
+    let liquidity = isqrt((amount_x as u128) * (amount_y as u128)) as u64;
+    require!(
+        liquidity > MINIMUM_LIQUIDITY,
+        AmmError::LiquidityLessThanMinimum
+    );
+    Ok(liquidity)
+}
+
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    let mut x = n;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
@@ -0,0 +1,102 @@
+use crate::curves::concentrated::mul_div;
+use crate::errors::AmmError;
+use anchor_lang::prelude::*;
+
+// two coin StableSwap invariant:
+// A * n^n * (x + y) + D = A * n^n * D + D^(n + 1) / (n^n * x * y), with n = 2
+
+const N_COINS: u128 = 2;
+const MAX_ITERATIONS: usize = 255;
+
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 1_000_000;
+pub const MAX_AMP_CHANGE: u64 = 10; // a single ramp can move A by at most this factor
+pub const MIN_RAMP_DURATION: i64 = 86_400;
+
+// solve the invariant for D with Newton's method
+pub fn compute_d(amp: u64, x: u64, y: u64) -> Result<u128> {
+    let (x, y) = (x as u128, y as u128);
+    let sum = x + y;
+    if sum == 0 {
+        return Ok(0);
+    }
+
+    let ann = (amp as u128) * N_COINS * N_COINS;
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // d_p = D^3 / (n^n * x * y), divided once over the 256 bit product: rounding
+        // D^2 / x first gets scaled up by D / y and can keep Newton cycling when the
+        // pool is unbalanced
+        let d_squared = d.checked_mul(d).ok_or(AmmError::Overflow)?;
+        let d_p = mul_div(d_squared, d, x * y, false)? / (N_COINS * N_COINS);
+
+        let prev = d;
+        let numerator = ann
+            .checked_mul(sum)
+            .and_then(|v| v.checked_add(d_p.checked_mul(N_COINS)?))
+            .ok_or(AmmError::Overflow)?;
+        let denominator = (ann - 1)
+            .checked_mul(d)
+            .and_then(|v| v.checked_add(d_p.checked_mul(N_COINS + 1)?))
+            .ok_or(AmmError::Overflow)?;
+        d = mul_div(numerator, d, denominator, false)?;
+
+        if d.abs_diff(prev) <= 1 {
+            return Ok(d);
+        }
+    }
+    err!(AmmError::CurveError)
+}
+
+// solve the invariant for the other reserve once one reserve is `x`
+pub fn compute_y(amp: u64, x: u128, d: u128) -> Result<u128> {
+    require!(x != 0, AmmError::ZeroBalance);
+
+    let ann = (amp as u128) * N_COINS * N_COINS;
+    // c = D^3 / (n^n * x * Ann), b = x + D / Ann
+    let d_squared = d.checked_mul(d).ok_or(AmmError::Overflow)?;
+    let c = mul_div(d_squared, d, x * N_COINS, false)? / (ann * N_COINS);
+    let b = x + d / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let prev = y;
+        let numerator = y
+            .checked_mul(y)
+            .and_then(|v| v.checked_add(c))
+            .ok_or(AmmError::Overflow)?;
+        let denominator = (2 * y + b).checked_sub(d).ok_or(AmmError::Underflow)?;
+        y = numerator / denominator;
+
+        if y.abs_diff(prev) <= 1 {
+            return Ok(y);
+        }
+    }
+    err!(AmmError::CurveError)
+}
+
+// amount of the output reserve released for `amount_in` of the input reserve, after fees
+pub fn swap(
+    amp: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    fee: u16,
+) -> Result<(u64, u64)> {
+    let fee_amount = (amount_in as u128 * fee as u128 / 10_000) as u64;
+    let amount_in_after_fee = amount_in - fee_amount;
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = (reserve_in as u128)
+        .checked_add(amount_in_after_fee as u128)
+        .ok_or(AmmError::Overflow)?;
+    let new_reserve_out = compute_y(amp, new_reserve_in, d)?;
+
+    // round against the trader
+    let amount_out = (reserve_out as u128)
+        .checked_sub(new_reserve_out)
+        .and_then(|v| v.checked_sub(1))
+        .ok_or(AmmError::InsufficientBalance)?;
+
+    Ok((amount_out as u64, fee_amount))
+}
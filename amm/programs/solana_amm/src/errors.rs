@@ -39,6 +39,32 @@ pub enum AmmError {
     InsufficientBalance,
     #[msg("Zero balance.")]
     ZeroBalance,
+    #[msg("Mint extension not supported by this pool.")]
+    UnsupportedMintExtension,
+    #[msg("Not enough oracle history for this window.")]
+    InsufficientOracleHistory,
+    #[msg("Invalid amplification coefficient.")]
+    InvalidAmplification,
+    #[msg("Not supported by this pool's curve.")]
+    UnsupportedCurve,
+    #[msg("Invalid swap route.")]
+    InvalidRoute,
+    #[msg("A flash loan is in progress.")]
+    FlashLoanActive,
+    #[msg("Flash loan is not repaid in this transaction.")]
+    FlashLoanNotRepaid,
+    #[msg("No flash loan to repay.")]
+    NoFlashLoan,
+    #[msg("Invalid tick.")]
+    InvalidTick,
+    #[msg("Invalid tick array.")]
+    InvalidTickArray,
+    #[msg("Invalid price.")]
+    InvalidPrice,
+    #[msg("A token account is required for every side that isn't native SOL.")]
+    MissingTokenAccount,
+    #[msg("Mints must be in canonical order, mint_x < mint_y.")]
+    InvalidMintOrder,
 }
 
 impl From<CurveError> for AmmError {
@@ -53,4 +79,4 @@ impl From<CurveError> for AmmError {
             CurveError::SlippageLimitExceeded => AmmError::SlippageExceeded,
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,175 @@
+use anchor_lang::prelude::*;
+
+use crate::state::CurveType;
+
+// reserves are the vault balances without uncollected protocol fees, all after the change
+
+#[event]
+pub struct PoolInitialized {
+    pub pool: Pubkey,
+    pub maker: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub seed: u64,
+    pub fee: u16,
+    pub curve: CurveType,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct LiquidityAdded {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_x: u64, // sent by the user
+    pub amount_y: u64,
+    pub lp_amount: u64, // minted
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_x: u64, // sent to the user
+    pub amount_y: u64,
+    pub lp_amount: u64, // burnt
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct Swapped {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub is_x: bool,      // true if X went in and Y came out
+    pub amount_in: u64,  // sent by the user
+    pub amount_out: u64, // sent to the user
+    pub fee: u64,        // part of the input charged as swap fee
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct PoolLocked {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub locked: bool,
+}
+
+#[event]
+pub struct FeeUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub fee: u16,
+    pub protocol_fee: u16,
+    pub flash_fee: u16,
+}
+
+#[event]
+pub struct TreasuryUpdated {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+}
+
+#[event]
+pub struct AuthorityUpdated {
+    pub pool: Pubkey,
+    pub authority: Option<Pubkey>,
+    pub pending_authority: Option<Pubkey>,
+}
+
+#[event]
+pub struct AmpRampUpdated {
+    pub pool: Pubkey,
+    pub amp_initial: u64,
+    pub amp_target: u64,
+    pub ramp_start: i64,
+    pub ramp_end: i64,
+}
+
+#[event]
+pub struct ProtocolFeesCollected {
+    pub pool: Pubkey,
+    pub treasury: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+#[event]
+pub struct FlashBorrowed {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub is_x: bool,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FlashRepaid {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub is_x: bool,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct ConcentratedPoolInitialized {
+    pub pool: Pubkey,
+    pub maker: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub seed: u64,
+    pub fee: u16,
+    pub tick_spacing: u16,
+    pub sqrt_price: u128,
+    pub tick: i32,
+}
+
+#[event]
+pub struct PositionOpened {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+}
+
+#[event]
+pub struct PositionLiquidityChanged {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub liquidity_delta: i128,
+    pub amount_x: u64, // sent by or to the owner
+    pub amount_y: u64,
+    pub position_liquidity: u128,
+    pub pool_liquidity: u128, // liquidity in range of the current tick
+}
+
+#[event]
+pub struct PositionFeesCollected {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+}
+
+#[event]
+pub struct ConcentratedSwapped {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub is_x: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub sqrt_price: u128,
+    pub tick: i32,
+    pub liquidity: u128,
+}
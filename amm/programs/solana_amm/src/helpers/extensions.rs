@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType,
+        StateWithExtensions,
+    },
+    state::Mint,
+};
+
+use crate::errors::AmmError;
+
+// extensions a pool may opt into at initialize, as bits of `Config.allowed_extensions`
+pub const ALLOW_PERMANENT_DELEGATE: u8 = 1 << 0; // delegate can move tokens out of the vaults
+pub const ALLOW_DEFAULT_ACCOUNT_STATE: u8 = 1 << 1; // vaults may start out frozen
+pub const ALLOW_MINT_CLOSE_AUTHORITY: u8 = 1 << 2; // mint can be closed once supply is zero
+pub const ALLOW_CONFIDENTIAL_TRANSFER: u8 = 1 << 3; // balances can move outside of the pool's view
+
+// reject mints carrying extensions the pool cannot handle or did not opt into
+pub fn check_mint_extensions(mint: &AccountInfo, allowed: u8) -> Result<()> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    for extension in state.get_extension_types()? {
+        let allowed = match extension {
+            // transfers would need the hook's extra accounts and it could block withdrawals
+            ExtensionType::TransferHook | ExtensionType::NonTransferable => false,
+            ExtensionType::PermanentDelegate => allowed & ALLOW_PERMANENT_DELEGATE != 0,
+            ExtensionType::DefaultAccountState => allowed & ALLOW_DEFAULT_ACCOUNT_STATE != 0,
+            ExtensionType::MintCloseAuthority => allowed & ALLOW_MINT_CLOSE_AUTHORITY != 0,
+            ExtensionType::ConfidentialTransferMint
+            | ExtensionType::ConfidentialTransferFeeConfig => {
+                allowed & ALLOW_CONFIDENTIAL_TRANSFER != 0
+            }
+            _ => true,
+        };
+        require!(allowed, AmmError::UnsupportedMintExtension);
+    }
+    Ok(())
+}
+
+// fee withheld by the mint when `amount` is sent, zero for mints without TransferFee
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    with_transfer_fee_config(mint, |config, epoch| {
+        config.calculate_epoch_fee(epoch, amount)
+    })
+}
+
+// fee to add on top of `amount` so that exactly `amount` arrives
+pub fn inverse_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    with_transfer_fee_config(mint, |config, epoch| {
+        config.calculate_inverse_epoch_fee(epoch, amount)
+    })
+}
+
+// amount that arrives when `amount` is sent
+pub fn amount_after_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    amount
+        .checked_sub(transfer_fee(mint, amount)?)
+        .ok_or(AmmError::Underflow.into())
+}
+
+// amount to send so that exactly `amount` arrives
+pub fn amount_before_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    amount
+        .checked_add(inverse_transfer_fee(mint, amount)?)
+        .ok_or(AmmError::Overflow.into())
+}
+
+fn with_transfer_fee_config<F>(mint: &AccountInfo, f: F) -> Result<u64>
+where
+    F: FnOnce(&TransferFeeConfig, u64) -> Option<u64>,
+{
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+
+    match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => f(config, Clock::get()?.epoch).ok_or(AmmError::Overflow.into()),
+        Err(_) => Ok(0),
+    }
+}
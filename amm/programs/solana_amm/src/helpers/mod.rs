@@ -1,3 +1,6 @@
+pub mod extensions;
+pub mod native;
+
 #[macro_export]
 macro_rules! assert_non_zero {
     ($array:expr) => {
@@ -16,6 +19,15 @@ macro_rules! assert_not_locked {
     };
 }
 
+#[macro_export]
+macro_rules! assert_no_flash_loan {
+    ($active:expr) => {
+        if $active == true {
+            return err!(AmmError::FlashLoanActive);
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! assert_not_expired {
     ($expiration:expr) => {
@@ -0,0 +1,97 @@
+use crate::errors::AmmError;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::system_program::{create_account, transfer, CreateAccount, Transfer};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_interface::{
+    close_account, initialize_account3, sync_native, CloseAccount, InitializeAccount3, SyncNative,
+    TokenAccount,
+};
+
+// wrapped SOL, under either token program
+pub fn is_native(mint: &Pubkey) -> bool {
+    *mint == spl_token_2022::native_mint::ID || *mint == spl_token::native_mint::ID
+}
+
+// the user's token account for one side. It may only be left out on a native SOL side, which
+// is then paid in and out in lamports, Ok(None) here
+pub fn user_token_account<'info>(
+    account: &Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    mint: &Pubkey,
+) -> Result<Option<AccountInfo<'info>>> {
+    match account {
+        Some(account) => Ok(Some(account.to_account_info())),
+        None if is_native(mint) => Ok(None),
+        None => err!(AmmError::MissingTokenAccount),
+    }
+}
+
+// move `amount` lamports from `from` into the wrapped SOL account `to`
+pub fn wrap_sol<'info>(
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    amount: u64,
+    system_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+) -> Result<()> {
+    let accounts = Transfer {
+        from,
+        to: to.clone(),
+    };
+    transfer(CpiContext::new(system_program, accounts), amount)?;
+
+    let accounts = SyncNative { account: to };
+    sync_native(CpiContext::new(token_program, accounts))
+}
+
+// create the temporary wrapped SOL account at the user's `[b"wsol", user]` address, to pay a
+// native side out through. The user funds its rent and gets it back with `unwrap_sol`
+pub fn open_temporary_sol_account<'info>(
+    account: &Option<UncheckedAccount<'info>>,
+    bump: Option<u8>,
+    mint: AccountInfo<'info>,
+    user: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+) -> Result<AccountInfo<'info>> {
+    let (Some(account), Some(bump)) = (account, bump) else {
+        return err!(AmmError::MissingTokenAccount);
+    };
+    let account = account.to_account_info();
+
+    let space = spl_token::state::Account::LEN;
+    let accounts = CreateAccount {
+        from: user.clone(),
+        to: account.clone(),
+    };
+    let seeds: &[&[u8]] = &[b"wsol", user.key.as_ref(), &[bump]];
+    create_account(
+        CpiContext::new_with_signer(system_program, accounts, &[seeds]),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        token_program.key,
+    )?;
+
+    let accounts = InitializeAccount3 {
+        account: account.clone(),
+        mint,
+        authority: user,
+    };
+    initialize_account3(CpiContext::new(token_program, accounts))?;
+    Ok(account)
+}
+
+// close the temporary wrapped SOL account `account`, its whole balance and rent go to `owner`
+pub fn unwrap_sol<'info>(
+    account: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+) -> Result<()> {
+    let accounts = CloseAccount {
+        account,
+        destination: owner.clone(),
+        authority: owner,
+    };
+    close_account(CpiContext::new(token_program, accounts))
+}
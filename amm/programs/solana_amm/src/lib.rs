@@ -2,9 +2,12 @@ use anchor_lang::prelude::*;
 
 mod contexts;
 use contexts::*;
-mod errors;
+pub mod curves;
+pub mod errors;
+pub mod events;
 mod helpers;
-mod state;
+pub mod state;
+use state::{CurveType, Earnings, Twap};
 
 declare_id!("4H2ThJYpHHVVGxxU1UgTGMW9szXMM7U8TtvZtV8G2Hf3");
 
@@ -12,19 +15,33 @@ declare_id!("4H2ThJYpHHVVGxxU1UgTGMW9szXMM7U8TtvZtV8G2Hf3");
 pub mod amm {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         ctx: Context<Initialize>,
         seed: u64,
         fee: u16,
+        curve: CurveType,
+        amp: u64,                  // StableSwap amplification, ignored for ConstantProduct
+        authority: Option<Pubkey>, // None creates an immutable pool
+        allowed_extensions: u8,    // Token-2022 extensions this pool opts into
         amount_x: u64,
         amount_y: u64,
     ) -> Result<()> {
-        ctx.accounts
-            .init(seed, fee, ctx.bumps.config, ctx.bumps.mint_lp)?;
+        ctx.accounts.init(
+            seed,
+            fee,
+            curve,
+            amp,
+            authority,
+            allowed_extensions,
+            ctx.bumps.config,
+            ctx.bumps.mint_lp,
+            (ctx.bumps.registry, ctx.bumps.registry_page),
+        )?;
 
         ctx.accounts.deposit(amount_x, true)?;
         ctx.accounts.deposit(amount_y, false)?;
-        ctx.accounts.mint_lp(amount_x, amount_y)
+        ctx.accounts.mint_lp()
     }
 
     // deposit liquidity to mint LP tokens
@@ -35,6 +52,214 @@ pub mod amm {
         max_y: u64,  // max amount of Y we are willing to deposit
         expiration: i64,
     ) -> Result<()> {
-        ctx.accounts.deposit(amount, max_x, max_y, expiration)
+        ctx.accounts
+            .deposit(amount, max_x, max_y, expiration, ctx.bumps.position)
+    }
+
+    // deposit only one side, part of it is swapped into the other side to mint LP tokens
+    pub fn deposit_single(
+        ctx: Context<Deposit>,
+        is_x: bool,      // true if depositing X
+        amount: u64,     // amount of the token to deposit
+        min_lp_out: u64, // min amount of LP token we are willing to receive
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .deposit_single(is_x, amount, min_lp_out, expiration, ctx.bumps.position)
+    }
+
+    // swap one side of the pool for the other
+    pub fn swap(
+        ctx: Context<Swap>,
+        is_x: bool,  // true if depositing X and receiving Y
+        amount: u64, // amount of the input token to deposit
+        min: u64,    // min amount of the output token we are willing to receive
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .swap(is_x, amount, min, expiration, ctx.bumps.wsol)
+    }
+
+    // swap through several pools in a row, each hop's accounts are passed as remaining accounts
+    pub fn swap_route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+        amount: u64,  // amount of the first input token to deposit
+        min_out: u64, // min amount of the last output token we are willing to receive
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .swap_route(ctx.remaining_accounts, amount, min_out, expiration)
+    }
+
+    // borrow from one vault, a flash_repay on the same accounts must follow in this transaction
+    pub fn flash_borrow(
+        ctx: Context<FlashLoan>,
+        is_x: bool,  // true if borrowing X
+        amount: u64, // amount to borrow
+    ) -> Result<()> {
+        ctx.accounts.flash_borrow(is_x, amount)
+    }
+
+    // return the borrowed amount plus the flash fee, which stays with the LPs
+    pub fn flash_repay(ctx: Context<FlashLoan>) -> Result<()> {
+        ctx.accounts.flash_repay()
+    }
+
+    // burn LP tokens to withdraw liquidity
+    pub fn withdraw(
+        ctx: Context<Withdraw>,
+        amount: u64, // amount of LP token to burn
+        min_x: u64,  // min amount of X we are willing to receive
+        min_y: u64,  // min amount of Y we are willing to receive
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .withdraw(amount, min_x, min_y, expiration, ctx.bumps.wsol)
+    }
+
+    // burn LP tokens to withdraw liquidity as only one side of the pool
+    pub fn withdraw_single(
+        ctx: Context<Withdraw>,
+        is_x: bool,   // true if receiving X
+        amount: u64,  // amount of LP token to burn
+        min_out: u64, // min amount of the token we are willing to receive
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .withdraw_single(is_x, amount, min_out, expiration, ctx.bumps.wsol)
+    }
+
+    // pause all deposits, swaps and withdrawals
+    pub fn lock(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.lock()
+    }
+
+    pub fn unlock(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.unlock()
+    }
+
+    pub fn update_fee(ctx: Context<Update>, fee: u16) -> Result<()> {
+        ctx.accounts.update_fee(fee)
+    }
+
+    pub fn update_flash_fee(ctx: Context<Update>, flash_fee: u16) -> Result<()> {
+        ctx.accounts.update_flash_fee(flash_fee)
+    }
+
+    // time-weighted average prices over at least the last `window` seconds, as return data
+    pub fn observe(ctx: Context<Observe>, window: i64) -> Result<Twap> {
+        ctx.accounts.observe(window)
+    }
+
+    // fees earned by an LP position record since it was opened, as return data
+    pub fn lp_earnings(ctx: Context<LpEarnings>) -> Result<Earnings> {
+        ctx.accounts.lp_earnings()
+    }
+
+    // share of the swap fee kept by the protocol, in base points of `fee`
+    pub fn update_protocol_fee(ctx: Context<Update>, protocol_fee: u16) -> Result<()> {
+        ctx.accounts.update_protocol_fee(protocol_fee)
+    }
+
+    pub fn update_treasury(ctx: Context<Update>, treasury: Pubkey) -> Result<()> {
+        ctx.accounts.update_treasury(treasury)
+    }
+
+    // pay the accrued protocol fees out to the treasury, anyone can call it
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        ctx.accounts.collect_protocol_fees()
+    }
+
+    // move the StableSwap amplification to `target_amp` linearly until `ramp_end`
+    pub fn ramp_amp(ctx: Context<Update>, target_amp: u64, ramp_end: i64) -> Result<()> {
+        ctx.accounts.ramp_amp(target_amp, ramp_end)
+    }
+
+    pub fn stop_ramp_amp(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.stop_ramp_amp()
+    }
+
+    // first step of an authority transfer, None renounces the authority immediately
+    pub fn propose_authority(ctx: Context<Update>, new_authority: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.propose_authority(new_authority)
+    }
+
+    // second step of an authority transfer, signed by the proposed authority
+    pub fn accept_authority(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.accept_authority()
+    }
+
+    // create a concentrated liquidity pool at the Q64.64 `sqrt_price`, positions supply the liquidity
+    pub fn initialize_concentrated(
+        ctx: Context<InitializeConcentrated>,
+        seed: u64,
+        fee: u16,
+        tick_spacing: u16, // positions can only be bounded by multiples of this
+        sqrt_price: u128,  // Q64.64 square root of the initial price of X in Y
+        allowed_extensions: u8, // Token-2022 extensions this pool opts into
+    ) -> Result<()> {
+        ctx.accounts.init(
+            seed,
+            fee,
+            tick_spacing,
+            sqrt_price,
+            allowed_extensions,
+            ctx.bumps.pool,
+            (ctx.bumps.registry, ctx.bumps.registry_page),
+        )
+    }
+
+    // create the tick array starting at `start_tick`, anyone can pay for it
+    pub fn init_tick_array(ctx: Context<InitTickArray>, start_tick: i32) -> Result<()> {
+        ctx.accounts.init_tick_array(start_tick)
+    }
+
+    // open an empty position over [tick_lower, tick_upper)
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        ctx.accounts
+            .open_position(tick_lower, tick_upper, ctx.bumps.position)
+    }
+
+    pub fn increase_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity: u128, // liquidity to add to the position
+        max_x: u64,      // max amount of X we are willing to deposit
+        max_y: u64,      // max amount of Y we are willing to deposit
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .increase_liquidity(liquidity, max_x, max_y, expiration)
+    }
+
+    pub fn decrease_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity: u128, // liquidity to remove from the position
+        min_x: u64,      // min amount of X we are willing to receive
+        min_y: u64,      // min amount of Y we are willing to receive
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .decrease_liquidity(liquidity, min_x, min_y, expiration)
+    }
+
+    // pay out the swap fees a position has earned
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        ctx.accounts.collect_fees()
+    }
+
+    // swap against a concentrated pool, the tick arrays to cross are passed as remaining accounts
+    pub fn swap_concentrated<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapConcentrated<'info>>,
+        is_x: bool,  // true if depositing X and receiving Y
+        amount: u64, // amount of the input token to deposit
+        min: u64,    // min amount of the output token we are willing to receive
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .swap_concentrated(ctx.remaining_accounts, is_x, amount, min, expiration)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,217 @@
+use anchor_lang::prelude::*;
+
+use crate::curves::concentrated::{mul_shift_64, MAX_TICK, MIN_TICK};
+use crate::errors::AmmError;
+
+// kept small, a tick array is deserialized onto the stack
+pub const TICK_ARRAY_SIZE: usize = 16;
+
+// a pool whose liquidity is provided over price ranges instead of the full curve
+#[account]
+#[derive(InitSpace)]
+pub struct ConcentratedPool {
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub token_program_x: Pubkey,
+    pub token_program_y: Pubkey,
+    pub seed: u64,
+    pub fee: u16, // base point, goes entirely to the positions in range
+    pub tick_spacing: u16,
+    pub sqrt_price: u128,       // Q64.64 square root of the price of X in Y
+    pub tick: i32,              // tick at or below `sqrt_price`
+    pub liquidity: u128,        // liquidity of the positions whose range holds `tick`
+    pub fee_growth_x: u128,     // Q64.64 fees of X earned per unit of liquidity, ever
+    pub fee_growth_y: u128,     // Q64.64 fees of Y earned per unit of liquidity, ever
+    pub allowed_extensions: u8, // opt-in Token-2022 extensions, see helpers::extensions
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Tick {
+    pub liquidity_gross: u128, // liquidity of all positions bounded by this tick
+    pub liquidity_net: i128,   // liquidity added when the price crosses this tick upwards
+    pub fee_growth_outside_x: u128, // fee growth on the other side of this tick from the price
+    pub fee_growth_outside_y: u128,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TickArray {
+    pub pool: Pubkey,
+    pub start_tick: i32,
+    pub ticks: [Tick; TICK_ARRAY_SIZE],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_x: u128, // fee growth inside the range as of the last update
+    pub fee_growth_inside_y: u128,
+    pub fees_owed_x: u64,
+    pub fees_owed_y: u64,
+    pub bump: u8,
+}
+
+impl ConcentratedPool {
+    // ticks covered by one tick array
+    pub fn tick_array_span(&self) -> i32 {
+        TICK_ARRAY_SIZE as i32 * self.tick_spacing as i32
+    }
+
+    pub fn check_tick(&self, tick: i32) -> Result<()> {
+        require!(
+            (MIN_TICK..=MAX_TICK).contains(&tick) && tick % self.tick_spacing as i32 == 0,
+            AmmError::InvalidTick
+        );
+        Ok(())
+    }
+
+    // fee growth between two ticks, wrapping like the global counters
+    pub fn fee_growth_inside(
+        &self,
+        tick_lower: i32,
+        lower: &Tick,
+        tick_upper: i32,
+        upper: &Tick,
+    ) -> (u128, u128) {
+        let (below_x, below_y) = match self.tick >= tick_lower {
+            true => (lower.fee_growth_outside_x, lower.fee_growth_outside_y),
+            false => (
+                self.fee_growth_x.wrapping_sub(lower.fee_growth_outside_x),
+                self.fee_growth_y.wrapping_sub(lower.fee_growth_outside_y),
+            ),
+        };
+        let (above_x, above_y) = match self.tick < tick_upper {
+            true => (upper.fee_growth_outside_x, upper.fee_growth_outside_y),
+            false => (
+                self.fee_growth_x.wrapping_sub(upper.fee_growth_outside_x),
+                self.fee_growth_y.wrapping_sub(upper.fee_growth_outside_y),
+            ),
+        };
+        (
+            self.fee_growth_x
+                .wrapping_sub(below_x)
+                .wrapping_sub(above_x),
+            self.fee_growth_y
+                .wrapping_sub(below_y)
+                .wrapping_sub(above_y),
+        )
+    }
+
+    // fee growth inside a position's range, from the tick arrays holding its bounds
+    pub fn position_fee_growth_inside(
+        &self,
+        position: &Position,
+        array_lower: &TickArray,
+        array_upper: &TickArray,
+    ) -> Result<(u128, u128)> {
+        let lower =
+            array_lower.ticks[array_lower.tick_index(position.tick_lower, self.tick_spacing)?];
+        let upper =
+            array_upper.ticks[array_upper.tick_index(position.tick_upper, self.tick_spacing)?];
+        Ok(self.fee_growth_inside(position.tick_lower, &lower, position.tick_upper, &upper))
+    }
+
+    // whether liquidity in [tick_lower, tick_upper) is currently in range
+    pub fn is_active(&self, tick_lower: i32, tick_upper: i32) -> bool {
+        self.tick >= tick_lower && self.tick < tick_upper
+    }
+}
+
+impl Tick {
+    // add `delta` liquidity bounded by this tick, as the lower or the upper end of a range
+    pub fn update(
+        &mut self,
+        tick: i32,
+        pool: &ConcentratedPool,
+        delta: i128,
+        upper: bool,
+    ) -> Result<()> {
+        let gross = add_delta(self.liquidity_gross, delta)?;
+
+        // growth so far is attributed to the side below the price
+        if self.liquidity_gross == 0 && tick <= pool.tick {
+            self.fee_growth_outside_x = pool.fee_growth_x;
+            self.fee_growth_outside_y = pool.fee_growth_y;
+        }
+        self.liquidity_gross = gross;
+        self.liquidity_net = match upper {
+            true => self.liquidity_net.checked_sub(delta),
+            false => self.liquidity_net.checked_add(delta),
+        }
+        .ok_or(AmmError::Overflow)?;
+        Ok(())
+    }
+
+    // reset a tick no position is bounded by anymore. Only once the fees inside the range
+    // have been settled, its fee growth outside is still needed for that
+    pub fn clear_if_unused(&mut self) {
+        if self.liquidity_gross == 0 {
+            *self = Tick::default();
+        }
+    }
+
+    // the price moved to the other side of this tick
+    pub fn cross(&mut self, fee_growth_x: u128, fee_growth_y: u128) {
+        self.fee_growth_outside_x = fee_growth_x.wrapping_sub(self.fee_growth_outside_x);
+        self.fee_growth_outside_y = fee_growth_y.wrapping_sub(self.fee_growth_outside_y);
+    }
+}
+
+impl TickArray {
+    pub fn tick_index(&self, tick: i32, tick_spacing: u16) -> Result<usize> {
+        let offset = tick - self.start_tick;
+        let spacing = tick_spacing as i32;
+        require!(
+            offset >= 0 && offset % spacing == 0 && offset / spacing < TICK_ARRAY_SIZE as i32,
+            AmmError::InvalidTickArray
+        );
+        Ok((offset / spacing) as usize)
+    }
+}
+
+impl Position {
+    // credit the fees earned since the last update
+    pub fn update_fees(
+        &mut self,
+        fee_growth_inside_x: u128,
+        fee_growth_inside_y: u128,
+    ) -> Result<()> {
+        let earned_x = mul_shift_64(
+            self.liquidity,
+            fee_growth_inside_x.wrapping_sub(self.fee_growth_inside_x),
+        )?;
+        let earned_y = mul_shift_64(
+            self.liquidity,
+            fee_growth_inside_y.wrapping_sub(self.fee_growth_inside_y),
+        )?;
+
+        self.fees_owed_x = self
+            .fees_owed_x
+            .checked_add(u64::try_from(earned_x).map_err(|_| AmmError::Overflow)?)
+            .ok_or(AmmError::Overflow)?;
+        self.fees_owed_y = self
+            .fees_owed_y
+            .checked_add(u64::try_from(earned_y).map_err(|_| AmmError::Overflow)?)
+            .ok_or(AmmError::Overflow)?;
+        self.fee_growth_inside_x = fee_growth_inside_x;
+        self.fee_growth_inside_y = fee_growth_inside_y;
+        Ok(())
+    }
+}
+
+pub fn add_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    match delta < 0 {
+        true => liquidity
+            .checked_sub(delta.unsigned_abs())
+            .ok_or(AmmError::Underflow.into()),
+        false => liquidity
+            .checked_add(delta as u128)
+            .ok_or(AmmError::Overflow.into()),
+    }
+}
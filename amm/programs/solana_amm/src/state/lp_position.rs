@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::state::Config;
+
+// optional record of the LP tokens a user minted through the pool, to report fees earned
+#[account]
+#[derive(InitSpace)]
+pub struct LpPosition {
+    pub config: Pubkey,
+    pub owner: Pubkey,
+    pub liquidity: u64, // LP tokens deposited through this record and not withdrawn yet
+    pub fee_per_share_x: u128, // Config.fee_per_share_x as of the last update
+    pub fee_per_share_y: u128, // Config.fee_per_share_y as of the last update
+    pub fees_earned_x: u64,
+    pub fees_earned_y: u64,
+    pub opened_at: i64,
+    pub bump: u8,
+}
+
+// fees earned by a position since it was opened, as return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct Earnings {
+    pub fees_x: u64,
+    pub fees_y: u64,
+    pub liquidity: u64,
+    pub opened_at: i64,
+}
+
+impl LpPosition {
+    // fees earned up to now, without updating the record
+    pub fn earnings(&self, config: &Config) -> Result<Earnings> {
+        let earned = |per_share: u128, last: u128| {
+            let amount = (self.liquidity as u128)
+                .checked_mul(per_share.wrapping_sub(last))
+                .ok_or(AmmError::Overflow)?
+                >> 64;
+            u64::try_from(amount).map_err(|_| AmmError::Overflow)
+        };
+
+        Ok(Earnings {
+            fees_x: self
+                .fees_earned_x
+                .checked_add(earned(config.fee_per_share_x, self.fee_per_share_x)?)
+                .ok_or(AmmError::Overflow)?,
+            fees_y: self
+                .fees_earned_y
+                .checked_add(earned(config.fee_per_share_y, self.fee_per_share_y)?)
+                .ok_or(AmmError::Overflow)?,
+            liquidity: self.liquidity,
+            opened_at: self.opened_at,
+        })
+    }
+
+    // settle the fees earned so far, must run before `liquidity` changes
+    pub fn checkpoint(&mut self, config: &Config) -> Result<()> {
+        let earnings = self.earnings(config)?;
+        self.fees_earned_x = earnings.fees_x;
+        self.fees_earned_y = earnings.fees_y;
+        self.fee_per_share_x = config.fee_per_share_x;
+        self.fee_per_share_y = config.fee_per_share_y;
+        Ok(())
+    }
+}
@@ -1,13 +1,123 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::AmmError;
+
+pub mod oracle;
+pub use oracle::*;
+
+pub mod concentrated;
+pub use concentrated::*;
+
+pub mod lp_position;
+pub use lp_position::*;
+
+pub mod registry;
+pub use registry::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CurveType {
+    ConstantProduct,
+    StableSwap,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct Config {
+    pub authority: Option<Pubkey>,
+    pub pending_authority: Option<Pubkey>,
     pub mint_x: Pubkey,
     pub mint_y: Pubkey,
+    pub token_program_x: Pubkey,
+    pub token_program_y: Pubkey,
     pub seed: u64,
-    pub fee: u16, // base point, 100% -> 100.00
+    pub curve: CurveType,
+    pub amp_initial: u64, // StableSwap amplification at the start of the current ramp
+    pub amp_target: u64,  // StableSwap amplification at the end of the current ramp
+    pub amp_ramp_start: i64,
+    pub amp_ramp_end: i64,
+    pub fee: u16,          // base point, 100% -> 100.00
+    pub protocol_fee: u16, // share of `fee` kept by the protocol, base point
+    pub treasury: Pubkey,
+    pub flash_fee: u16,           // fee on flash loans, base point, goes to LPs
+    pub protocol_fees_x: u64,     // protocol fees held in vault_x, not part of the reserves
+    pub protocol_fees_y: u64,     // protocol fees held in vault_y, not part of the reserves
+    pub fee_per_share_x: u128,    // Q64.64 LP fees of X earned per LP token, ever
+    pub fee_per_share_y: u128,    // Q64.64 LP fees of Y earned per LP token, ever
+    pub allowed_extensions: u8,   // opt-in Token-2022 extensions, see helpers::extensions
+    pub price_x_cumulative: u128, // Q64.64 price of X in Y, summed per second
+    pub price_y_cumulative: u128, // Q64.64 price of Y in X, summed per second
+    pub last_update: i64,
+    pub observations: [Observation; OBSERVATIONS],
+    pub observation_index: u8,
     pub bump: u8,
     pub lp_bump: u8,
     pub locked: bool,
+    pub flash_loan_active: bool, // set between flash_borrow and flash_repay
+    pub flash_loan_is_x: bool,
+    pub flash_loan_amount: u64,
+}
+
+impl Config {
+    // StableSwap amplification, linearly ramped from `amp_initial` to `amp_target`
+    pub fn amp_at(&self, now: i64) -> u64 {
+        if now >= self.amp_ramp_end || self.amp_ramp_end <= self.amp_ramp_start {
+            return self.amp_target;
+        }
+
+        let elapsed = now.saturating_sub(self.amp_ramp_start).max(0) as u128;
+        let duration = (self.amp_ramp_end - self.amp_ramp_start) as u128;
+        let (initial, target) = (self.amp_initial as u128, self.amp_target as u128);
+        match target > initial {
+            true => (initial + (target - initial) * elapsed / duration) as u64,
+            false => (initial - (initial - target) * elapsed / duration) as u64,
+        }
+    }
+
+    // vault balances minus the protocol fees that have not been collected yet
+    pub fn reserves(&self, vault_x: u64, vault_y: u64) -> Result<(u64, u64)> {
+        let x = vault_x
+            .checked_sub(self.protocol_fees_x)
+            .ok_or(AmmError::Underflow)?;
+        let y = vault_y
+            .checked_sub(self.protocol_fees_y)
+            .ok_or(AmmError::Underflow)?;
+        Ok((x, y))
+    }
+
+    // the protocol's share of a swap fee
+    pub fn protocol_fee_amount(&self, swap_fee: u64) -> Result<u64> {
+        Ok((swap_fee as u128)
+            .checked_mul(self.protocol_fee as u128)
+            .ok_or(AmmError::Overflow)?
+            .checked_div(10_000)
+            .ok_or(AmmError::Overflow)? as u64)
+    }
+
+    // split a swap fee charged on the input side between the protocol and `supply` LP tokens
+    pub fn accrue_fees(&mut self, swap_fee: u64, is_x: bool, supply: u64) -> Result<()> {
+        let amount = self.protocol_fee_amount(swap_fee)?;
+
+        let accrued = match is_x {
+            true => &mut self.protocol_fees_x,
+            false => &mut self.protocol_fees_y,
+        };
+        *accrued = accrued.checked_add(amount).ok_or(AmmError::Overflow)?;
+
+        self.accrue_lp_fee(swap_fee - amount, is_x, supply)
+    }
+
+    // fees left in the reserves, tracked per LP token for position reporting
+    pub fn accrue_lp_fee(&mut self, amount: u64, is_x: bool, supply: u64) -> Result<()> {
+        if amount == 0 || supply == 0 {
+            return Ok(());
+        }
+
+        let per_share = ((amount as u128) << 64) / supply as u128;
+        let accrued = match is_x {
+            true => &mut self.fee_per_share_x,
+            false => &mut self.fee_per_share_y,
+        };
+        *accrued = accrued.wrapping_add(per_share);
+        Ok(())
+    }
 }
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::state::Config;
+
+pub const OBSERVATIONS: usize = 24;
+pub const OBSERVATION_INTERVAL: i64 = 300; // seconds between two stored observations
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct Observation {
+    pub timestamp: i64,
+    pub price_x_cumulative: u128,
+    pub price_y_cumulative: u128,
+}
+
+// time-weighted average prices, Q64.64 fixed point
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct Twap {
+    pub price_x: u128, // price of X in Y
+    pub price_y: u128, // price of Y in X
+    pub window: i64,   // seconds actually covered, at least the requested window
+}
+
+impl Config {
+    pub fn init_oracle(&mut self) -> Result<()> {
+        self.last_update = Clock::get()?.unix_timestamp;
+        self.observations[0].timestamp = self.last_update;
+        Ok(())
+    }
+
+    // accumulate the prices of the reserves as they were since the last update,
+    // must run before the reserves change
+    pub fn update_oracle(&mut self, reserve_x: u64, reserve_y: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let elapsed = now.saturating_sub(self.last_update);
+        if elapsed <= 0 {
+            return Ok(());
+        }
+
+        let (price_x_cumulative, price_y_cumulative) =
+            self.cumulative_prices(reserve_x, reserve_y, elapsed);
+        self.price_x_cumulative = price_x_cumulative;
+        self.price_y_cumulative = price_y_cumulative;
+        self.last_update = now;
+
+        let last = self.observations[self.observation_index as usize];
+        if now - last.timestamp >= OBSERVATION_INTERVAL {
+            self.observation_index = ((self.observation_index as usize + 1) % OBSERVATIONS) as u8;
+            self.observations[self.observation_index as usize] = Observation {
+                timestamp: now,
+                price_x_cumulative,
+                price_y_cumulative,
+            };
+        }
+        Ok(())
+    }
+
+    // average prices over at least the last `window` seconds
+    pub fn observe(&self, reserve_x: u64, reserve_y: u64, window: i64) -> Result<Twap> {
+        require!(window > 0, AmmError::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let (price_x_now, price_y_now) =
+            self.cumulative_prices(reserve_x, reserve_y, now.saturating_sub(self.last_update));
+
+        // newest observation that is at least `window` old
+        let start = (0..OBSERVATIONS)
+            .map(|i| {
+                self.observations
+                    [(self.observation_index as usize + OBSERVATIONS - i) % OBSERVATIONS]
+            })
+            .find(|o| o.timestamp != 0 && now - o.timestamp >= window)
+            .ok_or(AmmError::InsufficientOracleHistory)?;
+
+        let elapsed = now - start.timestamp;
+        Ok(Twap {
+            price_x: price_x_now.wrapping_sub(start.price_x_cumulative) / elapsed as u128,
+            price_y: price_y_now.wrapping_sub(start.price_y_cumulative) / elapsed as u128,
+            window: elapsed,
+        })
+    }
+
+    // accumulators wrap on overflow, only differences between two of them are meaningful
+    fn cumulative_prices(&self, reserve_x: u64, reserve_y: u64, elapsed: i64) -> (u128, u128) {
+        if elapsed <= 0 || reserve_x == 0 || reserve_y == 0 {
+            return (self.price_x_cumulative, self.price_y_cumulative);
+        }
+
+        let price_x = ((reserve_y as u128) << 64) / reserve_x as u128;
+        let price_y = ((reserve_x as u128) << 64) / reserve_y as u128;
+        (
+            self.price_x_cumulative
+                .wrapping_add(price_x.wrapping_mul(elapsed as u128)),
+            self.price_y_cumulative
+                .wrapping_add(price_y.wrapping_mul(elapsed as u128)),
+        )
+    }
+}
@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::AmmError;
+use crate::state::CurveType;
+
+pub const REGISTRY_PAGE_SIZE: u32 = 32;
+
+// every pool created for one mint pair, constant product, StableSwap or concentrated, so
+// routers can find them without guessing seeds.
+// The pools themselves are listed in RegistryPage accounts, filled in creation order
+#[account]
+#[derive(InitSpace)]
+pub struct PoolRegistry {
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub pools: u32, // pools registered over all pages
+    pub bump: u8,
+}
+
+// up to REGISTRY_PAGE_SIZE pools of a pair, page `n` holds pools n * REGISTRY_PAGE_SIZE on
+#[account]
+#[derive(InitSpace)]
+pub struct RegistryPage {
+    pub registry: Pubkey,
+    pub page: u32,
+    #[max_len(REGISTRY_PAGE_SIZE)]
+    pub pools: Vec<RegisteredPool>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct RegisteredPool {
+    pub pool: Pubkey, // the Config, or the ConcentratedPool
+    pub seed: u64,
+    pub fee: u16, // fee at creation, the authority may change it later
+    pub kind: PoolKind,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum PoolKind {
+    ConstantProduct,
+    StableSwap,
+    Concentrated,
+}
+
+impl From<CurveType> for PoolKind {
+    fn from(curve: CurveType) -> PoolKind {
+        match curve {
+            CurveType::ConstantProduct => PoolKind::ConstantProduct,
+            CurveType::StableSwap => PoolKind::StableSwap,
+        }
+    }
+}
+
+impl PoolRegistry {
+    // the page the next pool of the pair goes to
+    pub fn next_page(&self) -> u32 {
+        self.pools / REGISTRY_PAGE_SIZE
+    }
+
+    // add `pool` to `page`, which must be the next page, both may have just been created
+    pub fn register(
+        &mut self,
+        key: Pubkey,
+        mints: (Pubkey, Pubkey),
+        page: &mut RegistryPage,
+        pool: RegisteredPool,
+        bumps: (u8, u8),
+    ) -> Result<()> {
+        // first pool of the pair
+        if self.pools == 0 {
+            (self.mint_x, self.mint_y) = mints;
+            self.bump = bumps.0;
+        }
+        if page.pools.is_empty() {
+            page.registry = key;
+            page.page = self.next_page();
+            page.bump = bumps.1;
+        }
+
+        page.pools.push(pool);
+        self.pools = self.pools.checked_add(1).ok_or(AmmError::Overflow)?;
+        Ok(())
+    }
+}
@@ -1,16 +1,657 @@
 import * as anchor from "@coral-xyz/anchor";
-import { Program } from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
 import { SolanaAmm } from "../target/types/solana_amm";
+import {
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  SystemProgram,
+  Transaction,
+} from "@solana/web3.js";
+import {
+  MINT_SIZE,
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  createInitializeMint2Instruction,
+  createMintToInstruction,
+  getAssociatedTokenAddressSync,
+  getMinimumBalanceForRentExemptMint,
+} from "@solana/spl-token";
+import { randomBytes } from "crypto";
+import { expect } from "chai";
 
 describe("solana_amm", () => {
-  // Configure the client to use the local cluster.
   anchor.setProvider(anchor.AnchorProvider.env());
 
+  const provider = anchor.getProvider();
+
+  const connection = provider.connection;
+
   const program = anchor.workspace.SolanaAmm as Program<SolanaAmm>;
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+  const tokenProgram = TOKEN_PROGRAM_ID;
+
+  const confirm = async (signature: string): Promise<string> => {
+    const block = await connection.getLatestBlockhash();
+    await connection.confirmTransaction({
+      signature,
+      ...block,
+    });
+    return signature;
+  };
+
+  const log = async (signature: string): Promise<string> => {
+    console.log(
+      `Your transaction signature: https://explorer.solana.com/transaction/${signature}?cluster=custom&customUrl=${connection.rpcEndpoint}`
+    );
+    return signature;
+  };
+
+  const expectError = async (tx: Promise<unknown>, code: string) => {
+    try {
+      await tx;
+    } catch (e) {
+      // transactions sent without `.rpc()` only carry the program logs
+      const error = e instanceof anchor.AnchorError ? e : anchor.AnchorError.parse(e.logs);
+      expect(error?.error.errorCode.code).to.equal(code);
+      return;
+    }
+    expect.fail(`expected ${code}`);
+  };
+
+  const balance = async (ata: PublicKey): Promise<number> =>
+    Number((await connection.getTokenAccountBalance(ata)).value.amount);
+
+  const supply = async (): Promise<number> =>
+    Number((await connection.getTokenSupply(mintLp)).value.amount);
+
+  const never = new BN("9223372036854775807");
+
+  const seed = new BN(randomBytes(8));
+
+  const [maker, user] = Array.from({ length: 2 }, () => Keypair.generate());
+
+  // pools only accept their mints in canonical order
+  const [mintX, mintY] = Array.from({ length: 2 }, () => Keypair.generate()).sort((a, b) =>
+    a.publicKey.toBuffer().compare(b.publicKey.toBuffer())
+  );
+
+  const [makerAtaX, makerAtaY, userAtaX, userAtaY] = [maker, user]
+    .map((a) =>
+      [mintX, mintY].map((m) =>
+        getAssociatedTokenAddressSync(m.publicKey, a.publicKey, false, tokenProgram)
+      )
+    )
+    .flat();
+
+  // the accounts of the pool at `seed`
+  const poolAccounts = (seed: BN) => {
+    const config = PublicKey.findProgramAddressSync(
+      [Buffer.from("amm"), mintX.publicKey.toBuffer(), mintY.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+    const mintLp = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_lp"), config.toBuffer()],
+      program.programId
+    )[0];
+
+    const [vaultX, vaultY] = [mintX, mintY].map((m) =>
+      getAssociatedTokenAddressSync(m.publicKey, config, true, tokenProgram)
+    );
+
+    const [makerAtaLp, userAtaLp] = [maker, user].map((a) =>
+      getAssociatedTokenAddressSync(mintLp, a.publicKey, false, tokenProgram)
+    );
+
+    // every pool of the pair fits on the registry's first page
+    const registry = PublicKey.findProgramAddressSync(
+      [Buffer.from("registry"), mintX.publicKey.toBuffer(), mintY.publicKey.toBuffer()],
+      program.programId
+    )[0];
+    const registryPage = PublicKey.findProgramAddressSync(
+      [Buffer.from("registry"), mintX.publicKey.toBuffer(), mintY.publicKey.toBuffer(), new BN(0).toArrayLike(Buffer, "le", 4)],
+      program.programId
+    )[0];
+
+    // holds the minimum liquidity locked at initialize
+    const vaultLp = getAssociatedTokenAddressSync(mintLp, config, true, tokenProgram);
+
+    return {
+      maker: maker.publicKey,
+      user: user.publicKey,
+      mintX: mintX.publicKey,
+      mintY: mintY.publicKey,
+      mintLp,
+      vaultX,
+      vaultY,
+      vaultLp,
+      makerAtaX,
+      makerAtaY,
+      makerAtaLp,
+      userAtaX,
+      userAtaY,
+      userAtaLp,
+      config,
+      registry,
+      registryPage,
+      tokenProgram,
+      tokenProgramX: tokenProgram,
+      tokenProgramY: tokenProgram,
+      // skip the optional LP position record
+      position: null,
+      // every side here is an SPL token, nothing to pay out in lamports
+      wsol: null,
+    };
+  };
+
+  // Accounts
+  const accounts = poolAccounts(seed);
+  const { config, mintLp, vaultX, vaultY, userAtaLp } = accounts;
+
+  // the same accounts with the maker signing as the pool's authority
+  const asMaker = { ...accounts, user: maker.publicKey };
+
+  it("Airdrop and create mints", async () => {
+    let lamports = await getMinimumBalanceForRentExemptMint(connection);
+    let tx = new Transaction();
+    tx.instructions = [
+      ...[maker, user].map((account) =>
+        SystemProgram.transfer({
+          fromPubkey: provider.publicKey,
+          toPubkey: account.publicKey,
+          lamports: 10 * LAMPORTS_PER_SOL,
+        })
+      ),
+      ...[mintX, mintY].map((mint) =>
+        SystemProgram.createAccount({
+          fromPubkey: provider.publicKey,
+          newAccountPubkey: mint.publicKey,
+          lamports,
+          space: MINT_SIZE,
+          programId: tokenProgram,
+        })
+      ),
+      ...[mintX, mintY].map((mint) =>
+        createInitializeMint2Instruction(mint.publicKey, 6, maker.publicKey, null, tokenProgram)
+      ),
+      ...[
+        { mint: mintX.publicKey, owner: maker.publicKey, ata: makerAtaX },
+        { mint: mintY.publicKey, owner: maker.publicKey, ata: makerAtaY },
+        { mint: mintX.publicKey, owner: user.publicKey, ata: userAtaX },
+        { mint: mintY.publicKey, owner: user.publicKey, ata: userAtaY },
+      ]
+      .flatMap((x) => [
+        createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, x.ata, x.owner, x.mint, tokenProgram),
+        createMintToInstruction(x.mint, x.ata, maker.publicKey, 1e9, undefined, tokenProgram),
+      ])
+    ];
+
+    await provider.sendAndConfirm(tx, [mintX, mintY, maker]).then(log);
+  });
+
+  it("Initialize", async () => {
+    await program.methods
+      .initialize(seed, 30, { constantProduct: {} }, new BN(0), maker.publicKey, 0, new BN(10e6), new BN(40e6))
+      .accounts({ ...accounts })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  it("Swap X for Y", async () => {
+    const [x, y] = await Promise.all([vaultX, vaultY].map(balance));
+
+    await program.methods
+      .swap(true, new BN(1e6), new BN(1), never)
+      .accounts({ ...accounts })
+      .signers([user])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    const [newX, newY] = await Promise.all([vaultX, vaultY].map(balance));
+    expect(newX - x).to.equal(1e6);
+    expect(await balance(userAtaY)).to.equal(1e9 + y - newY);
+    // the fee stays in the pool, so the product only grows
+    expect(newX * newY).to.be.at.least(x * y);
+  });
+
+  it("Swap Y for X", async () => {
+    const [x, y] = await Promise.all([vaultX, vaultY].map(balance));
+
+    await program.methods
+      .swap(false, new BN(1e6), new BN(1), never)
+      .accounts({ ...accounts })
+      .signers([user])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    const [newX, newY] = await Promise.all([vaultX, vaultY].map(balance));
+    expect(newY - y).to.equal(1e6);
+    expect(newX * newY).to.be.at.least(x * y);
+  });
+
+  it("Swap below the min", async () => {
+    await expectError(
+      program.methods
+        .swap(true, new BN(1e6), new BN(10e6), never)
+        .accounts({ ...accounts })
+        .signers([user])
+        .rpc(),
+      "SlippageExceeded"
+    );
+  });
+
+  it("Swap after the expiration", async () => {
+    await expectError(
+      program.methods
+        .swap(true, new BN(1e6), new BN(1), new BN(1))
+        .accounts({ ...accounts })
+        .signers([user])
+        .rpc(),
+      "OfferExpired"
+    );
+  });
+
+  it("Deposit", async () => {
+    const lp = Math.floor((await supply()) / 100);
+
+    await program.methods
+      .deposit(new BN(lp), new BN(1e9), new BN(1e9), never)
+      .accounts({ ...accounts })
+      .signers([user])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    expect(await balance(userAtaLp)).to.equal(lp);
+  });
+
+  it("Withdraw", async () => {
+    const lp = Math.floor((await balance(userAtaLp)) / 2);
+    const [x, y, lpSupply] = await Promise.all([balance(vaultX), balance(vaultY), supply()]);
+    const [userX, userY] = await Promise.all([userAtaX, userAtaY].map(balance));
+
+    await program.methods
+      .withdraw(new BN(lp), new BN(1), new BN(1), never)
+      .accounts({ ...accounts })
+      .signers([user])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    const [newX, newY, newSupply] = await Promise.all([balance(vaultX), balance(vaultY), supply()]);
+    expect(newSupply).to.equal(lpSupply - lp);
+    expect(await balance(userAtaX)).to.equal(userX + x - newX);
+    expect(await balance(userAtaY)).to.equal(userY + y - newY);
+    // burning a share of the LP supply pays out at most that share of each reserve
+    const share = (reserve: number, paid: number) =>
+      new BN(paid).mul(new BN(lpSupply)).lte(new BN(reserve).mul(new BN(lp)));
+    expect(share(x, x - newX)).to.be.true;
+    expect(share(y, y - newY)).to.be.true;
+  });
+
+  it("Withdraw more than held", async () => {
+    const lp = await balance(userAtaLp);
+    await expectError(
+      program.methods
+        .withdraw(new BN(lp + 1), new BN(1), new BN(1), never)
+        .accounts({ ...accounts })
+        .signers([user])
+        .rpc(),
+      "InsufficientBalance"
+    );
+  });
+
+  it("Withdraw below the min", async () => {
+    const lp = await balance(userAtaLp);
+    await expectError(
+      program.methods
+        .withdraw(new BN(lp), new BN(1e9), new BN(1), never)
+        .accounts({ ...accounts })
+        .signers([user])
+        .rpc(),
+      "SlippageExceeded"
+    );
+  });
+
+  it("Update the fee to 100%", async () => {
+    await expectError(
+      program.methods
+        .updateFee(10_000)
+        .accounts({ ...asMaker })
+        .signers([maker])
+        .rpc(),
+      "InvalidFee"
+    );
+  });
+
+  it("Lock", async () => {
+    await program.methods
+      .lock()
+      .accounts({ ...asMaker })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    await expectError(
+      program.methods
+        .swap(true, new BN(1e6), new BN(1), never)
+        .accounts({ ...accounts })
+        .signers([user])
+        .rpc(),
+      "PoolLocked"
+    );
+
+    await program.methods
+      .unlock()
+      .accounts({ ...asMaker })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    await program.methods
+      .swap(true, new BN(1e6), new BN(1), never)
+      .accounts({ ...accounts })
+      .signers([user])
+      .rpc()
+      .then(confirm);
+  });
+
+  it("Lock as someone else", async () => {
+    await expectError(
+      program.methods
+        .lock()
+        .accounts({ ...accounts })
+        .signers([user])
+        .rpc(),
+      "InvalidAuthority"
+    );
+  });
+
+  it("Update fee", async () => {
+    await program.methods
+      .updateFee(100)
+      .accounts({ ...asMaker })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    expect((await program.account.config.fetch(config)).fee).to.equal(100);
+  });
+
+  it("Transfer the authority", async () => {
+    await program.methods
+      .proposeAuthority(user.publicKey)
+      .accounts({ ...asMaker })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    // nothing changes hands until the proposed authority accepts
+    await expectError(
+      program.methods
+        .updateFee(30)
+        .accounts({ ...accounts })
+        .signers([user])
+        .rpc(),
+      "InvalidAuthority"
+    );
+
+    await program.methods
+      .acceptAuthority()
+      .accounts({ ...accounts })
+      .signers([user])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    await expectError(
+      program.methods
+        .updateFee(30)
+        .accounts({ ...asMaker })
+        .signers([maker])
+        .rpc(),
+      "InvalidAuthority"
+    );
+
+    // and back to the maker
+    await program.methods
+      .proposeAuthority(maker.publicKey)
+      .accounts({ ...accounts })
+      .signers([user])
+      .rpc()
+      .then(confirm);
+
+    await program.methods
+      .acceptAuthority()
+      .accounts({ ...asMaker })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    const { authority, pendingAuthority } = await program.account.config.fetch(config);
+    expect(authority.equals(maker.publicKey)).to.be.true;
+    expect(pendingAuthority).to.be.null;
+  });
+
+  it("Accept an authority nobody proposed", async () => {
+    await expectError(
+      program.methods
+        .acceptAuthority()
+        .accounts({ ...accounts })
+        .signers([user])
+        .rpc(),
+      "NoAuthoritySet"
+    );
+  });
+
+  const renouncedSeed = new BN(randomBytes(8));
+  const renouncedAccounts = poolAccounts(renouncedSeed);
+
+  it("Renounce the authority", async () => {
+    await program.methods
+      .initialize(renouncedSeed, 30, { constantProduct: {} }, new BN(0), maker.publicKey, 0, new BN(10e6), new BN(40e6))
+      .accounts({ ...renouncedAccounts })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    await program.methods
+      .proposeAuthority(null)
+      .accounts({ ...renouncedAccounts, user: maker.publicKey })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    await expectError(
+      program.methods
+        .lock()
+        .accounts({ ...renouncedAccounts, user: maker.publicKey })
+        .signers([maker])
+        .rpc(),
+      "NoAuthoritySet"
+    );
+  });
+
+  const treasury = Keypair.generate();
+
+  const [treasuryAtaX, treasuryAtaY] = [mintX, mintY].map((m) =>
+    getAssociatedTokenAddressSync(m.publicKey, treasury.publicKey, false, tokenProgram)
+  );
+
+  it("Update protocol fee and treasury", async () => {
+    await expectError(
+      program.methods
+        .updateProtocolFee(10_000)
+        .accounts({ ...asMaker })
+        .signers([maker])
+        .rpc(),
+      "InvalidFee"
+    );
+
+    await program.methods
+      .updateProtocolFee(5_000)
+      .accounts({ ...asMaker })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    await program.methods
+      .updateTreasury(treasury.publicKey)
+      .accounts({ ...asMaker })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  it("Collect protocol fees", async () => {
+    await program.methods
+      .swap(true, new BN(1e6), new BN(1), never)
+      .accounts({ ...accounts })
+      .signers([user])
+      .rpc()
+      .then(confirm);
+
+    // half of the 1% swap fee, paid in X
+    const { protocolFeesX, protocolFeesY } = await program.account.config.fetch(config);
+    expect(protocolFeesX.toNumber()).to.be.within(1, 5_000);
+    expect(protocolFeesY.toNumber()).to.equal(0);
+
+    // anyone can collect, it's paid to the treasury either way
+    await program.methods
+      .collectProtocolFees()
+      .accounts({ ...accounts, treasury: treasury.publicKey, treasuryAtaX, treasuryAtaY })
+      .signers([user])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    expect(await balance(treasuryAtaX)).to.equal(protocolFeesX.toNumber());
+    expect(await balance(treasuryAtaY)).to.equal(0);
+
+    await expectError(
+      program.methods
+        .collectProtocolFees()
+        .accounts({ ...accounts, treasury: treasury.publicKey, treasuryAtaX, treasuryAtaY })
+        .signers([user])
+        .rpc(),
+      "ZeroBalance"
+    );
+  });
+
+  it("Collect protocol fees after renouncing", async () => {
+    const seed = new BN(randomBytes(8));
+    const pool = poolAccounts(seed);
+
+    await program.methods
+      .initialize(seed, 30, { constantProduct: {} }, new BN(0), maker.publicKey, 0, new BN(10e6), new BN(40e6))
+      .accounts({ ...pool })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    await program.methods
+      .updateProtocolFee(5_000)
+      .accounts({ ...pool, user: maker.publicKey })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    await program.methods
+      .proposeAuthority(null)
+      .accounts({ ...pool, user: maker.publicKey })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    await program.methods
+      .swap(true, new BN(1e6), new BN(1), never)
+      .accounts({ ...pool })
+      .signers([user])
+      .rpc()
+      .then(confirm);
+
+    // nobody can change the fee any more, but what it accrues still reaches the treasury,
+    // which is left at the maker
+    const { protocolFeesX } = await program.account.config.fetch(pool.config);
+    expect(protocolFeesX.toNumber()).to.be.above(0);
+    const makerX = await balance(makerAtaX);
+
+    await program.methods
+      .collectProtocolFees()
+      .accounts({ ...pool, treasury: maker.publicKey, treasuryAtaX: makerAtaX, treasuryAtaY: makerAtaY })
+      .signers([user])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    expect(await balance(makerAtaX)).to.equal(makerX + protocolFeesX.toNumber());
+  });
+
+  it("Update the flash loan fee to 100%", async () => {
+    await expectError(
+      program.methods
+        .updateFlashFee(10_000)
+        .accounts({ ...asMaker })
+        .signers([maker])
+        .rpc(),
+      "InvalidFee"
+    );
+  });
+
+  const flashAccounts = { ...accounts, instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY };
+
+  const flashLoan = async (...instructions: Promise<anchor.web3.TransactionInstruction>[]) =>
+    provider.sendAndConfirm(new Transaction().add(...(await Promise.all(instructions))), [user]);
+
+  const borrow = (amount: number) =>
+    program.methods.flashBorrow(true, new BN(amount)).accounts({ ...flashAccounts }).instruction();
+
+  const repay = () => program.methods.flashRepay().accounts({ ...flashAccounts }).instruction();
+
+  it("Flash loan", async () => {
+    const { flashFee } = await program.account.config.fetch(config);
+    const [x, userX] = await Promise.all([vaultX, userAtaX].map(balance));
+
+    await flashLoan(borrow(1e6), repay()).then(log);
+
+    // the fee is rounded up and stays in the vault
+    const fee = Math.ceil((1e6 * flashFee) / 10_000);
+    expect(await balance(vaultX)).to.equal(x + fee);
+    expect(await balance(userAtaX)).to.equal(userX - fee);
+    expect((await program.account.config.fetch(config)).flashLoanActive).to.be.false;
+  });
+
+  it("Flash borrow without repaying", async () => {
+    await expectError(flashLoan(borrow(1e6)), "FlashLoanNotRepaid");
+  });
+
+  it("Flash borrow more than the reserve", async () => {
+    await expectError(flashLoan(borrow(await balance(vaultX)), repay()), "InsufficientBalance");
+  });
+
+  it("Swap during a flash loan", async () => {
+    const swap = program.methods
+      .swap(true, new BN(1e6), new BN(1), never)
+      .accounts({ ...accounts })
+      .instruction();
+
+    await expectError(flashLoan(borrow(1e6), swap, repay()), "FlashLoanActive");
+  });
+
+  it("Repay without borrowing", async () => {
+    await expectError(flashLoan(repay()), "NoFlashLoan");
   });
 });
@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address_with_program_id, Create},
+    token_2022,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TransferChecked,
+    },
+};
+
+use crate::{errors::EscrowError, BasketLeg};
+
+// remaining accounts per basket leg: mint, from, to, token_program
+pub const LEG_ACCOUNTS: usize = 4;
+
+pub struct Leg<'info> {
+    pub mint: &'info AccountInfo<'info>,
+    pub from: &'info AccountInfo<'info>,
+    pub to: &'info AccountInfo<'info>,
+    pub token_program: &'info AccountInfo<'info>,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+// match the remaining accounts against the manifest, in order
+pub fn legs<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    manifest: &[BasketLeg],
+) -> Result<Vec<Leg<'info>>> {
+    require!(
+        accounts.len() == manifest.len() * LEG_ACCOUNTS,
+        EscrowError::InvalidLegAccounts
+    );
+
+    accounts
+        .chunks(LEG_ACCOUNTS)
+        .zip(manifest)
+        .map(|(accounts, leg)| {
+            let (mint, token_program) = (&accounts[0], &accounts[3]);
+            require_keys_eq!(mint.key(), leg.mint, EscrowError::InvalidLegAccounts);
+            require!(
+                token_program.key() == anchor_spl::token::ID
+                    || token_program.key() == token_2022::ID,
+                EscrowError::InvalidLegAccounts
+            );
+            require_keys_eq!(
+                *mint.owner,
+                token_program.key(),
+                EscrowError::InvalidLegAccounts
+            );
+
+            Ok(Leg {
+                mint,
+                from: &accounts[1],
+                to: &accounts[2],
+                token_program,
+                amount: leg.amount,
+                decimals: InterfaceAccount::<Mint>::try_from(mint)?.decimals,
+            })
+        })
+        .collect()
+}
+
+impl<'info> Leg<'info> {
+    pub fn check_from(&self, owner: &Pubkey) -> Result<()> {
+        require_keys_eq!(
+            self.from.key(),
+            self.ata(owner),
+            EscrowError::InvalidLegAccounts
+        );
+        Ok(())
+    }
+
+    // `to` is always the owner's associated token account, created if needed
+    pub fn create_to(
+        &self,
+        owner: &AccountInfo<'info>,
+        payer: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        associated_token_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        require_keys_eq!(
+            self.to.key(),
+            self.ata(owner.key),
+            EscrowError::InvalidLegAccounts
+        );
+
+        let accounts = Create {
+            payer: payer.clone(),
+            associated_token: self.to.clone(),
+            authority: owner.clone(),
+            mint: self.mint.clone(),
+            system_program: system_program.clone(),
+            token_program: self.token_program.clone(),
+        };
+        let ctx = CpiContext::new(associated_token_program.clone(), accounts);
+
+        create_idempotent(ctx)
+    }
+
+    pub fn balance(&self) -> Result<u64> {
+        Ok(InterfaceAccount::<TokenAccount>::try_from(self.from)?.amount)
+    }
+
+    pub fn transfer(
+        &self,
+        authority: &AccountInfo<'info>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let accounts = TransferChecked {
+            from: self.from.clone(),
+            mint: self.mint.clone(),
+            to: self.to.clone(),
+            authority: authority.clone(),
+        };
+        let ctx = CpiContext::new_with_signer(self.token_program.clone(), accounts, signer_seeds);
+
+        transfer_checked(ctx, amount, self.decimals)
+    }
+
+    // close the `from` vault once emptied
+    pub fn close_from(
+        &self,
+        authority: &AccountInfo<'info>,
+        destination: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let accounts = CloseAccount {
+            account: self.from.clone(),
+            destination: destination.clone(),
+            authority: authority.clone(),
+        };
+        let ctx = CpiContext::new_with_signer(self.token_program.clone(), accounts, signer_seeds);
+
+        close_account(ctx)
+    }
+
+    fn ata(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, self.mint.key, self.token_program.key)
+    }
+}
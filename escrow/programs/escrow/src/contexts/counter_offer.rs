@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{errors::EscrowError, CounterOffer, Escrow};
+
+#[derive(Accounts)]
+pub struct MakeCounter<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        has_one = mint_a,
+        has_one = mint_b,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+    #[account(
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = taker,
+        space = 8 + CounterOffer::INIT_SPACE,
+        seeds = [b"counter", escrow.key().as_ref(), taker.key().as_ref()],
+        bump
+    )]
+    pub counter: Box<Account<'info, CounterOffer>>,
+    #[account(
+        init,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = counter,
+        associated_token::token_program = token_program
+    )]
+    pub counter_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeCounter<'info> {
+    pub fn save_counter(&mut self, offer: u64, proof: &[[u8; 32]], bump: u8) -> Result<()> {
+        require!(
+            !self.escrow.is_expired(Clock::get()?.unix_timestamp),
+            EscrowError::OfferExpired
+        );
+        require!(
+            self.escrow.can_take(&self.taker.key(), proof),
+            EscrowError::TakerNotAllowed
+        );
+
+        self.counter.set_inner(CounterOffer {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            offer,
+            expected: self.vault.amount,
+            bump,
+        });
+        Ok(())
+    }
+
+    pub fn deposit(&mut self, offer: u64) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: self.counter_vault.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+
+        transfer_checked(cpi_ctx, offer, self.mint_b.decimals)
+    }
+}
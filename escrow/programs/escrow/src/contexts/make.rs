@@ -5,7 +5,7 @@ use anchor_spl::{
     token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
-use crate::Escrow;
+use crate::{errors::EscrowError, Escrow};
 
 #[derive(Accounts)]
 #[instruction(seed: u64)]
@@ -49,13 +49,40 @@ pub struct Make<'info> {
 }
 
 impl<'info> Make<'info> {
-    pub fn save_escrow(&mut self, seed: u64, receive: u64, bumps: &MakeBumps) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn save_escrow(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        expires_at: Option<i64>,
+        taker: Option<Pubkey>,
+        allow_list: Option<[u8; 32]>,
+        bumps: &MakeBumps,
+    ) -> Result<()> {
+        require!(
+            taker.is_none() || allow_list.is_none(),
+            EscrowError::InvalidTakerRestriction
+        );
+        if let Some(expires_at) = expires_at {
+            require!(
+                expires_at > Clock::get()?.unix_timestamp,
+                EscrowError::InvalidExpiry
+            );
+        }
+
+        // a transfer fee mint delivers less than was sent, keep what actually reached the vault
+        self.vault.reload()?;
         self.escrow.set_inner(Escrow {
             seed,
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
+            deposit: self.vault.amount,
             receive,
+            received: 0,
+            expires_at,
+            taker,
+            allow_list,
             bump: bumps.escrow,
         });
         Ok(())
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::associated_token::AssociatedToken;
+
+use super::basket::legs;
+use crate::{Basket, BasketLeg};
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeBasket<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + Basket::INIT_SPACE,
+        seeds = [b"basket", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub basket: Account<'info, Basket>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeBasket<'info> {
+    pub fn save_basket(
+        &mut self,
+        seed: u64,
+        offered: Vec<BasketLeg>,
+        requested: Vec<BasketLeg>,
+        bumps: &MakeBasketBumps,
+    ) -> Result<()> {
+        Basket::check_legs(&offered)?;
+        Basket::check_legs(&requested)?;
+
+        self.basket.set_inner(Basket {
+            seed,
+            maker: self.maker.key(),
+            offered,
+            requested,
+            bump: bumps.basket,
+        });
+        Ok(())
+    }
+
+    // remaining accounts per offered leg: mint, maker token account, vault, token program
+    pub fn deposit(&self, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let basket = self.basket.to_account_info();
+        let maker = self.maker.to_account_info();
+
+        for leg in legs(accounts, &self.basket.offered)? {
+            leg.create_to(
+                &basket,
+                &maker,
+                &self.system_program.to_account_info(),
+                &self.associated_token_program.to_account_info(),
+            )?;
+            leg.transfer(&maker, leg.amount, &[])?;
+        }
+        Ok(())
+    }
+}
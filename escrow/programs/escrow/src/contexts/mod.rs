@@ -1,8 +1,34 @@
+pub mod accept_counter;
+pub use accept_counter::*;
+
+pub mod amend;
+pub use amend::*;
+
+mod basket;
+
+pub mod cancel_counter;
+pub use cancel_counter::*;
+
+pub mod counter_offer;
+pub use counter_offer::*;
+
+pub mod crank_expired;
+pub use crank_expired::*;
+
 pub mod make;
 pub use make::*;
 
+pub mod make_basket;
+pub use make_basket::*;
+
 pub mod refund;
 pub use refund::*;
 
+pub mod refund_basket;
+pub use refund_basket::*;
+
 pub mod take;
 pub use take::*;
+
+pub mod take_basket;
+pub use take_basket::*;
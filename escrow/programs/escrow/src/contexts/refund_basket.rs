@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::associated_token::AssociatedToken;
+
+use super::basket::legs;
+use crate::Basket;
+
+#[derive(Accounts)]
+pub struct RefundBasket<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"basket", maker.key().as_ref(), basket.seed.to_le_bytes().as_ref()],
+        bump = basket.bump
+    )]
+    pub basket: Account<'info, Basket>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundBasket<'info> {
+    // remaining accounts per offered leg: mint, vault, maker token account, token program
+    pub fn refund_and_close_vaults(&self, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let maker = self.maker.to_account_info();
+        let basket = self.basket.to_account_info();
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"basket",
+            self.maker.to_account_info().key.as_ref(),
+            &self.basket.seed.to_le_bytes()[..],
+            &[self.basket.bump],
+        ]];
+
+        for leg in legs(accounts, &self.basket.offered)? {
+            leg.check_from(&basket.key())?;
+            leg.create_to(
+                &maker,
+                &maker,
+                &self.system_program.to_account_info(),
+                &self.associated_token_program.to_account_info(),
+            )?;
+            leg.transfer(&basket, leg.balance()?, &signer_seeds)?;
+            leg.close_from(&basket, &maker, &signer_seeds)?;
+        }
+        Ok(())
+    }
+}
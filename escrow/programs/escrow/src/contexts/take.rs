@@ -8,7 +8,7 @@ use anchor_spl::{
     },
 };
 
-use crate::Escrow;
+use crate::{errors::EscrowError, Escrow};
 
 #[derive(Accounts)]
 pub struct Take<'info> {
@@ -43,14 +43,13 @@ pub struct Take<'info> {
     pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
-        close = maker,
         has_one = maker,
         has_one = mint_a,
         has_one = mint_b,
         seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
         bump = escrow.bump
     )]
-    escrow: Account<'info, Escrow>,
+    pub escrow: Account<'info, Escrow>,
     #[account(
         mut,
         associated_token::mint = mint_a,
@@ -64,7 +63,43 @@ pub struct Take<'info> {
 }
 
 impl<'info> Take<'info> {
-    pub fn deposit(&mut self) -> Result<()> {
+    // pay whatever is left of the offer, as long as it is no more than `max_pay`
+    pub fn fill_remaining(&mut self, max_pay: u64, proof: &[[u8; 32]]) -> Result<()> {
+        let amount = self.escrow.remaining();
+        require!(amount <= max_pay, EscrowError::TermsChanged);
+        self.fill(amount, 0, proof)
+    }
+
+    // the bounds keep an amend landing just before the fill from repricing it
+    pub fn fill(&mut self, amount: u64, min_release: u64, proof: &[[u8; 32]]) -> Result<()> {
+        require!(
+            self.escrow.can_take(&self.taker.key(), proof),
+            EscrowError::TakerNotAllowed
+        );
+        require!(
+            !self.escrow.is_expired(Clock::get()?.unix_timestamp),
+            EscrowError::OfferExpired
+        );
+        require!(amount <= self.escrow.remaining(), EscrowError::FillTooLarge);
+
+        if amount < self.escrow.remaining() {
+            let release = self.escrow.release_for(amount)?;
+            require!(release != 0, EscrowError::FillTooSmall);
+            require!(release >= min_release, EscrowError::TermsChanged);
+            self.deposit(amount)?;
+            self.escrow.received += amount;
+            return self.withdraw(release);
+        }
+
+        // the last fill takes everything left in the vault
+        require!(self.vault.amount >= min_release, EscrowError::TermsChanged);
+        self.deposit(amount)?;
+        self.withdraw(self.vault.amount)?;
+        self.close_vault()?;
+        self.escrow.close(self.maker.to_account_info())
+    }
+
+    pub fn deposit(&mut self, amount: u64) -> Result<()> {
         let transfer_accounts = TransferChecked {
             from: self.taker_ata_b.to_account_info(),
             mint: self.mint_b.to_account_info(),
@@ -74,10 +109,10 @@ impl<'info> Take<'info> {
 
         let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
 
-        transfer_checked(cpi_ctx, self.escrow.receive, self.mint_b.decimals)
+        transfer_checked(cpi_ctx, amount, self.mint_b.decimals)
     }
 
-    pub fn withdraw_and_close_vault(&mut self) -> Result<()> {
+    pub fn withdraw(&mut self, amount: u64) -> Result<()> {
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.to_account_info().key.as_ref(),
@@ -98,7 +133,16 @@ impl<'info> Take<'info> {
             &signer_seeds,
         );
 
-        transfer_checked(ctx, self.vault.amount, self.mint_a.decimals)?;
+        transfer_checked(ctx, amount, self.mint_a.decimals)
+    }
+
+    pub fn close_vault(&mut self) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
 
         let accounts = CloseAccount {
             account: self.vault.to_account_info(),
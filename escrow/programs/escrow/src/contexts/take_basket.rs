@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::associated_token::AssociatedToken;
+
+use super::basket::{legs, LEG_ACCOUNTS};
+use crate::Basket;
+
+#[derive(Accounts)]
+pub struct TakeBasket<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"basket", maker.key().as_ref(), basket.seed.to_le_bytes().as_ref()],
+        bump = basket.bump
+    )]
+    pub basket: Account<'info, Basket>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeBasket<'info> {
+    // remaining accounts, requested legs first: mint, taker token account, maker token
+    // account, token program. Then offered legs: mint, vault, taker token account, token program
+    pub fn pay_and_withdraw(&self, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let split = (self.basket.requested.len() * LEG_ACCOUNTS).min(accounts.len());
+        let (requested, offered) = accounts.split_at(split);
+
+        let taker = self.taker.to_account_info();
+        let maker = self.maker.to_account_info();
+        let basket = self.basket.to_account_info();
+        let system_program = self.system_program.to_account_info();
+        let associated_token_program = self.associated_token_program.to_account_info();
+
+        for leg in legs(requested, &self.basket.requested)? {
+            leg.create_to(&maker, &taker, &system_program, &associated_token_program)?;
+            leg.transfer(&taker, leg.amount, &[])?;
+        }
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"basket",
+            self.maker.to_account_info().key.as_ref(),
+            &self.basket.seed.to_le_bytes()[..],
+            &[self.basket.bump],
+        ]];
+
+        for leg in legs(offered, &self.basket.offered)? {
+            leg.check_from(&basket.key())?;
+            leg.create_to(&taker, &taker, &system_program, &associated_token_program)?;
+            leg.transfer(&basket, leg.balance()?, &signer_seeds)?;
+            // the maker paid for the vaults
+            leg.close_from(&basket, &maker, &signer_seeds)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum EscrowError {
+    #[msg("Fill is larger than what is left of the offer.")]
+    FillTooLarge,
+    #[msg("Fill is too small to release any of the deposit.")]
+    FillTooSmall,
+    #[msg("Offer has expired.")]
+    OfferExpired,
+    #[msg("Offer has not expired yet.")]
+    OfferNotExpired,
+    #[msg("Expiry must be in the future.")]
+    InvalidExpiry,
+    #[msg("Offer is restricted to other takers.")]
+    TakerNotAllowed,
+    #[msg("Offer can be restricted to one taker or to an allow-list, not both.")]
+    InvalidTakerRestriction,
+    #[msg("Basket sides need 1 to 5 distinct mints with non zero amounts.")]
+    InvalidBasket,
+    #[msg("Remaining accounts don't match the basket manifest.")]
+    InvalidLegAccounts,
+    #[msg("Offer was partly filled since the counter-offer was made.")]
+    CounterOutdated,
+    #[msg("Offer terms changed beyond what the taker accepted.")]
+    TermsChanged,
+    #[msg("Overflow detected.")]
+    Overflow,
+}
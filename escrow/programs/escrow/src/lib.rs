@@ -3,6 +3,8 @@ use anchor_lang::prelude::*;
 pub mod contexts;
 use contexts::*;
 
+pub mod errors;
+
 pub mod state;
 pub use state::*;
 
@@ -12,17 +14,96 @@ declare_id!("46Nc64twkNsYQeAgUEC2inU26g8LyXtDCRqZ9R8d61gu");
 pub mod anchor_escrow {
     use super::*;
 
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, receive: u64) -> Result<()> {
+    pub fn make(
+        ctx: Context<Make>,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+        expires_at: Option<i64>, // None keeps the offer open until taken or refunded
+        taker: Option<Pubkey>,   // Some makes the offer private to that taker
+        allow_list: Option<[u8; 32]>, // Merkle root of allowed takers, see Escrow::can_take
+    ) -> Result<()> {
         ctx.accounts.deposit(deposit)?;
-        ctx.accounts.save_escrow(seed, receive, &ctx.bumps)
+        ctx.accounts
+            .save_escrow(seed, receive, expires_at, taker, allow_list, &ctx.bumps)
     }
 
     pub fn refund(ctx: Context<Refund>) -> Result<()> {
         ctx.accounts.refund_and_close_vault()
     }
 
-    pub fn take(ctx: Context<Take>) -> Result<()> {
-        ctx.accounts.deposit()?;
+    // anyone can return an expired offer's deposit and rent to the maker
+    pub fn crank_expired(ctx: Context<CrankExpired>) -> Result<()> {
+        ctx.accounts.return_and_close_vault()
+    }
+
+    // change the price of what is left, or add to the deposit, keeping the offer's address
+    pub fn amend(ctx: Context<Amend>, receive: Option<u64>, top_up: u64) -> Result<()> {
+        ctx.accounts.top_up(top_up)?;
+        ctx.accounts.amend(receive)
+    }
+
+    // bid `offer` of mint_b for everything left in the escrow, held until the maker accepts
+    // or the taker cancels
+    pub fn counter_offer(
+        ctx: Context<MakeCounter>,
+        offer: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.deposit(offer)?;
+        ctx.accounts.save_counter(offer, &proof, ctx.bumps.counter)
+    }
+
+    pub fn accept_counter(ctx: Context<AcceptCounter>) -> Result<()> {
+        ctx.accounts.deposit_and_close_counter_vault()?;
         ctx.accounts.withdraw_and_close_vault()
     }
+
+    pub fn cancel_counter(ctx: Context<CancelCounter>) -> Result<()> {
+        ctx.accounts.refund_and_close_vault()
+    }
+
+    // take whatever is left of the offer, `proof` is only needed for allow-listed offers
+    pub fn take(
+        ctx: Context<Take>,
+        max_pay: u64, // max amount of mint_b we are willing to pay for the rest of the vault
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.fill_remaining(max_pay, &proof)
+    }
+
+    // pay `amount` of mint_b for the matching share of the vault, the offer stays open
+    // until fully filled
+    pub fn take_partial(
+        ctx: Context<Take>,
+        amount: u64,
+        min_release: u64, // min amount of mint_a we are willing to receive for it
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.fill(amount, min_release, &proof)
+    }
+
+    // swap up to MAX_BASKET_LEGS mints for up to MAX_BASKET_LEGS others in one settlement, the
+    // offered mints are deposited into one vault each
+    pub fn make_basket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MakeBasket<'info>>,
+        seed: u64,
+        offered: Vec<BasketLeg>,
+        requested: Vec<BasketLeg>,
+    ) -> Result<()> {
+        ctx.accounts
+            .save_basket(seed, offered, requested, &ctx.bumps)?;
+        ctx.accounts.deposit(ctx.remaining_accounts)
+    }
+
+    pub fn refund_basket<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundBasket<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.refund_and_close_vaults(ctx.remaining_accounts)
+    }
+
+    // pay every requested leg and receive every offered leg, all or nothing
+    pub fn take_basket<'info>(ctx: Context<'_, '_, 'info, 'info, TakeBasket<'info>>) -> Result<()> {
+        ctx.accounts.pay_and_withdraw(ctx.remaining_accounts)
+    }
 }
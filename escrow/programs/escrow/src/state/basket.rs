@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::EscrowError;
+
+pub const MAX_BASKET_LEGS: usize = 5;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct BasketLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+// manifest of a basket offer, each offered mint sits in its own vault owned by the basket
+#[account]
+#[derive(InitSpace)]
+pub struct Basket {
+    pub seed: u64,
+    pub maker: Pubkey,
+    #[max_len(MAX_BASKET_LEGS)]
+    pub offered: Vec<BasketLeg>,
+    #[max_len(MAX_BASKET_LEGS)]
+    pub requested: Vec<BasketLeg>,
+    pub bump: u8,
+}
+
+impl Basket {
+    // every side holds 1 to MAX_BASKET_LEGS distinct mints with non zero amounts
+    pub fn check_legs(legs: &[BasketLeg]) -> Result<()> {
+        require!(
+            (1..=MAX_BASKET_LEGS).contains(&legs.len()),
+            EscrowError::InvalidBasket
+        );
+        for (i, leg) in legs.iter().enumerate() {
+            require!(leg.amount != 0, EscrowError::InvalidBasket);
+            require!(
+                legs[..i].iter().all(|other| other.mint != leg.mint),
+                EscrowError::InvalidBasket
+            );
+        }
+        Ok(())
+    }
+}
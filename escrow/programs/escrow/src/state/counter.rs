@@ -0,0 +1,12 @@
+use anchor_lang::prelude::*;
+
+// a taker's bid for everything left in an escrow, its mint_b waits in the counter's vault
+#[account]
+#[derive(InitSpace)]
+pub struct CounterOffer {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub offer: u64,    // mint_b deposited for the rest of the escrow
+    pub expected: u64, // mint_a in the escrow vault when the counter was made
+    pub bump: u8,
+}
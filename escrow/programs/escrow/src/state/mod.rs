@@ -1,4 +1,13 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::errors::EscrowError;
+
+pub mod basket;
+pub use basket::*;
+
+pub mod counter;
+pub use counter::*;
 
 #[account]
 #[derive(InitSpace)]
@@ -7,6 +16,127 @@ pub struct Escrow {
     pub maker: Pubkey,
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
-    pub receive: u64,
+    pub deposit: u64,                 // mint_a that reached the vault at make
+    pub receive: u64,                 // mint_b asked for the whole deposit
+    pub received: u64,                // mint_b paid by takers so far
+    pub expires_at: Option<i64>,      // None never expires
+    pub taker: Option<Pubkey>,        // the only taker of a private offer
+    pub allow_list: Option<[u8; 32]>, // Merkle root of the takers allowed to fill
     pub bump: u8,
 }
+
+impl Escrow {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    // `proof` is only read for allow-listed offers, sibling hashes from the taker's leaf up
+    pub fn can_take(&self, taker: &Pubkey, proof: &[[u8; 32]]) -> bool {
+        if self.taker.is_some_and(|allowed| allowed != *taker) {
+            return false;
+        }
+        match self.allow_list {
+            Some(root) => verify_proof(root, taker, proof),
+            None => true,
+        }
+    }
+
+    // mint_b still to be paid before the offer is filled
+    pub fn remaining(&self) -> u64 {
+        self.receive - self.received
+    }
+
+    // mint_a released to a taker paying `amount` of mint_b, pro rata to the price. Computed
+    // from the running total so rounding never adds up across fills, always in favor of the maker
+    pub fn release_for(&self, amount: u64) -> Result<u64> {
+        let received = self
+            .received
+            .checked_add(amount)
+            .ok_or(EscrowError::Overflow)?;
+        Ok(self.released(received)? - self.released(self.received)?)
+    }
+
+    // restart the pricing from the `vault` balance after any top up, asking `receive` for all
+    // of it, or the same price for what was left when None
+    pub fn amend(&mut self, receive: Option<u64>, vault: u64) -> Result<()> {
+        self.deposit = vault;
+        self.receive = receive.unwrap_or(self.remaining());
+        self.received = 0;
+        Ok(())
+    }
+
+    // mint_a released once takers paid `received` of mint_b in total
+    fn released(&self, received: u64) -> Result<u64> {
+        if self.receive == 0 {
+            return Ok(0);
+        }
+        let released = received as u128 * self.deposit as u128 / self.receive as u128;
+        u64::try_from(released).map_err(|_| error!(EscrowError::Overflow))
+    }
+}
+
+// leaves are the hash of a taker's key, each pair of nodes is hashed in sorted order so
+// the proof doesn't need to say which side the sibling is on
+fn verify_proof(root: [u8; 32], taker: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let leaf = hashv(&[taker.as_ref()]).to_bytes();
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        match node <= *sibling {
+            true => hashv(&[&node, sibling]),
+            false => hashv(&[sibling, &node]),
+        }
+        .to_bytes()
+    });
+    computed == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(key: &Pubkey) -> [u8; 32] {
+        hashv(&[key.as_ref()]).to_bytes()
+    }
+
+    fn pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        match a <= b {
+            true => hashv(&[&a, &b]),
+            false => hashv(&[&b, &a]),
+        }
+        .to_bytes()
+    }
+
+    // root = pair(pair(a, b), pair(c, d))
+    fn tree() -> ([Pubkey; 4], [u8; 32]) {
+        let keys = [(); 4].map(|_| Pubkey::new_unique());
+        let [a, b, c, d] = keys.map(|key| leaf(&key));
+        (keys, pair(pair(a, b), pair(c, d)))
+    }
+
+    #[test]
+    fn proofs_verify_for_every_leaf() {
+        let (keys, root) = tree();
+        let [a, b, c, d] = keys.map(|key| leaf(&key));
+        assert!(verify_proof(root, &keys[0], &[b, pair(c, d)]));
+        assert!(verify_proof(root, &keys[1], &[a, pair(c, d)]));
+        assert!(verify_proof(root, &keys[2], &[d, pair(a, b)]));
+        assert!(verify_proof(root, &keys[3], &[c, pair(a, b)]));
+    }
+
+    #[test]
+    fn proofs_fail_for_other_takers_and_wrong_siblings() {
+        let (keys, root) = tree();
+        let [a, b, c, d] = keys.map(|key| leaf(&key));
+        assert!(!verify_proof(root, &Pubkey::new_unique(), &[b, pair(c, d)]));
+        assert!(!verify_proof(root, &keys[0], &[c, pair(a, b)]));
+        assert!(!verify_proof(root, &keys[0], &[pair(c, d), b]));
+        assert!(!verify_proof(root, &keys[0], &[b]));
+        assert!(!verify_proof(root, &keys[0], &[]));
+    }
+
+    #[test]
+    fn a_single_taker_list_needs_no_proof() {
+        let taker = Pubkey::new_unique();
+        assert!(verify_proof(leaf(&taker), &taker, &[]));
+        assert!(!verify_proof(leaf(&taker), &Pubkey::new_unique(), &[]));
+    }
+}
@@ -18,7 +18,8 @@ import {
   getAssociatedTokenAddressSync,
   getMinimumBalanceForRentExemptMint,
 } from "@solana/spl-token";
-import { randomBytes } from "crypto";
+import { createHash, randomBytes } from "crypto";
+import { expect } from "chai";
 
 describe("anchor-escrow", () => {
   anchor.setProvider(anchor.AnchorProvider.env());
@@ -50,7 +51,7 @@ describe("anchor-escrow", () => {
 
   const seed = new BN(randomBytes(8));
 
-  const [maker, taker, mintA, mintB] = Array.from({ length: 4 }, () =>
+  const [maker, taker, outsider, mintA, mintB] = Array.from({ length: 5 }, () =>
     Keypair.generate()
   );
 
@@ -84,11 +85,44 @@ describe("anchor-escrow", () => {
     tokenProgram,
   }
 
+  // the same accounts for another escrow of the maker
+  const escrowAccounts = (seed: BN) => {
+    const escrow = PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+    const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+    return { ...accounts, escrow, vault };
+  };
+
+  // taker accounts of someone who isn't let into restricted offers
+  const outsiderAccounts = {
+    taker: outsider.publicKey,
+    takerAtaA: getAssociatedTokenAddressSync(mintA.publicKey, outsider.publicKey, false, tokenProgram),
+    takerAtaB: getAssociatedTokenAddressSync(mintB.publicKey, outsider.publicKey, false, tokenProgram),
+  };
+
+  const expectError = async (tx: Promise<unknown>, code: string) => {
+    try {
+      await tx;
+    } catch (e) {
+      expect(e.error?.errorCode?.code).to.equal(code);
+      return;
+    }
+    expect.fail(`expected ${code}`);
+  };
+
+  // the validator clock, which is what expiry is checked against
+  const now = async (): Promise<number> =>
+    connection.getBlockTime(await connection.getSlot());
+
+  const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
   it("Airdrop and create mints", async () => {
     let lamports = await getMinimumBalanceForRentExemptMint(connection);
     let tx = new Transaction();
     tx.instructions = [
-      ...[maker, taker].map((account) =>
+      ...[maker, taker, outsider].map((account) =>
         SystemProgram.transfer({
           fromPubkey: provider.publicKey,
           toPubkey: account.publicKey,
@@ -112,7 +146,9 @@ describe("anchor-escrow", () => {
         createInitializeMint2Instruction(x.mint, 6, x.authority, null, tokenProgram),
         createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, x.ata, x.authority, x.mint, tokenProgram),
         createMintToInstruction(x.mint, x.ata, x.authority, 1e9, undefined, tokenProgram),
-      ])
+      ]),
+      createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, outsiderAccounts.takerAtaB, outsider.publicKey, mintB.publicKey, tokenProgram),
+      createMintToInstruction(mintB.publicKey, outsiderAccounts.takerAtaB, taker.publicKey, 1e9, undefined, tokenProgram),
     ];
 
     await provider.sendAndConfirm(tx, [mintA, mintB, maker, taker]).then(log);
@@ -120,7 +156,7 @@ describe("anchor-escrow", () => {
 
   it("Make", async () => {
     await program.methods
-      .make(seed, new BN(1e6), new BN(1e6))
+      .make(seed, new BN(1e6), new BN(1e6), null, null, null)
       .accounts({ ...accounts })
       .signers([maker])
       .rpc()
@@ -138,10 +174,20 @@ describe("anchor-escrow", () => {
       .then(log);
   });
 
+  it("Take partial", async () => {
+    await program.methods
+      .takePartial(new BN(4e5), new BN(4e5), [])
+      .accounts({ ...accounts })
+      .signers([taker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
   it("Take", async () => {
     try {
     await program.methods
-      .take()
+      .take(new BN(6e5), [])
       .accounts({  ...accounts })
       .signers([taker])
       .rpc()
@@ -152,4 +198,371 @@ describe("anchor-escrow", () => {
       throw(e)
     }
   });
+
+  const amendSeed = new BN(randomBytes(8));
+  const amendAccounts = escrowAccounts(amendSeed);
+
+  it("Amend", async () => {
+    await program.methods
+      .make(amendSeed, new BN(1e6), new BN(1e6), null, null, null)
+      .accounts({ ...amendAccounts })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    await program.methods
+      .amend(new BN(2e6), new BN(0))
+      .accounts({ ...amendAccounts })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  // takes sent at the old price of 1:1 that land after the amend
+  it("Take partial at the old price", async () => {
+    await expectError(
+      program.methods
+        .takePartial(new BN(4e5), new BN(4e5), [])
+        .accounts({ ...amendAccounts })
+        .signers([taker])
+        .rpc(),
+      "TermsChanged"
+    );
+  });
+
+  it("Take at the old price", async () => {
+    await expectError(
+      program.methods
+        .take(new BN(1e6), [])
+        .accounts({ ...amendAccounts })
+        .signers([taker])
+        .rpc(),
+      "TermsChanged"
+    );
+  });
+
+  it("Take at the amended price", async () => {
+    await program.methods
+      .take(new BN(2e6), [])
+      .accounts({ ...amendAccounts })
+      .signers([taker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  const basket = PublicKey.findProgramAddressSync(
+    [Buffer.from("basket"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+    program.programId
+  )[0];
+
+  const basketVaultA = getAssociatedTokenAddressSync(mintA.publicKey, basket, true, tokenProgram);
+
+  // remaining accounts for one basket leg: mint, from, to, token program
+  const leg = (mint: PublicKey, from: PublicKey, to: PublicKey) =>
+    [mint, from, to, tokenProgram].map((pubkey) => ({
+      pubkey,
+      isSigner: false,
+      isWritable: !pubkey.equals(mint) && !pubkey.equals(tokenProgram),
+    }));
+
+  it("Make basket", async () => {
+    await program.methods
+      .makeBasket(
+        seed,
+        [{ mint: mintA.publicKey, amount: new BN(1e6) }],
+        [{ mint: mintB.publicKey, amount: new BN(1e6) }]
+      )
+      .accounts({ maker: maker.publicKey, basket })
+      .remainingAccounts(leg(mintA.publicKey, makerAtaA, basketVaultA))
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  it("Take basket", async () => {
+    await program.methods
+      .takeBasket()
+      .accounts({ taker: taker.publicKey, maker: maker.publicKey, basket })
+      .remainingAccounts([
+        ...leg(mintB.publicKey, takerAtaB, makerAtaB),
+        ...leg(mintA.publicKey, basketVaultA, takerAtaA),
+      ])
+      .signers([taker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  // the accounts of an escrow with the taker's counter-offer on it
+  const counterAccountsFor = (seed: BN) => {
+    const offer = escrowAccounts(seed);
+    const counter = PublicKey.findProgramAddressSync(
+      [Buffer.from("counter"), offer.escrow.toBuffer(), taker.publicKey.toBuffer()],
+      program.programId
+    )[0];
+    const counterVault = getAssociatedTokenAddressSync(mintB.publicKey, counter, true, tokenProgram);
+    return { ...offer, counter, counterVault };
+  };
+
+  const counterSeed = new BN(randomBytes(8));
+  const counterAccounts = counterAccountsFor(counterSeed);
+
+  const makeWithCounter = async (seed: BN, offer: BN) => {
+    await program.methods
+      .make(seed, new BN(1e6), new BN(1e6), null, null, null)
+      .accounts({ ...counterAccountsFor(seed) })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    await program.methods
+      .counterOffer(offer, [])
+      .accounts({ ...counterAccountsFor(seed) })
+      .signers([taker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  };
+
+  it("Counter offer", async () => {
+    await makeWithCounter(counterSeed, new BN(9e5));
+  });
+
+  it("Accept counter", async () => {
+    await program.methods
+      .acceptCounter()
+      .accounts({ ...counterAccounts })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  it("Cancel counter on an open offer", async () => {
+    const seed = new BN(randomBytes(8));
+    const cancelAccounts = counterAccountsFor(seed);
+    await makeWithCounter(seed, new BN(9e5));
+
+    await program.methods
+      .cancelCounter()
+      .accounts({ ...cancelAccounts })
+      .signers([taker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    expect(await connection.getAccountInfo(cancelAccounts.counter)).to.be.null;
+    expect(await connection.getAccountInfo(cancelAccounts.escrow)).to.not.be.null;
+  });
+
+  const outdatedSeed = new BN(randomBytes(8));
+  const outdatedAccounts = counterAccountsFor(outdatedSeed);
+
+  it("Accept counter after a partial fill", async () => {
+    await makeWithCounter(outdatedSeed, new BN(9e5));
+
+    await program.methods
+      .takePartial(new BN(4e5), new BN(4e5), [])
+      .accounts({ ...outdatedAccounts })
+      .signers([taker])
+      .rpc()
+      .then(confirm);
+
+    await expectError(
+      program.methods
+        .acceptCounter()
+        .accounts({ ...outdatedAccounts })
+        .signers([maker])
+        .rpc(),
+      "CounterOutdated"
+    );
+  });
+
+  it("Cancel counter on a closed offer", async () => {
+    await program.methods
+      .take(new BN(6e5), [])
+      .accounts({ ...outdatedAccounts })
+      .signers([taker])
+      .rpc()
+      .then(confirm);
+    expect(await connection.getAccountInfo(outdatedAccounts.escrow)).to.be.null;
+
+    await program.methods
+      .cancelCounter()
+      .accounts({ ...outdatedAccounts })
+      .signers([taker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    expect(await connection.getAccountInfo(outdatedAccounts.counter)).to.be.null;
+    expect(await connection.getAccountInfo(outdatedAccounts.counterVault)).to.be.null;
+  });
+
+  const expiringSeed = new BN(randomBytes(8));
+  const expiringAccounts = escrowAccounts(expiringSeed);
+  let expiresAt: number;
+
+  it("Crank before expiry", async () => {
+    expiresAt = (await now()) + 5;
+    await program.methods
+      .make(expiringSeed, new BN(1e6), new BN(1e6), new BN(expiresAt), null, null)
+      .accounts({ ...expiringAccounts })
+      .signers([maker])
+      .rpc()
+      .then(confirm);
+
+    await expectError(
+      program.methods
+        .crankExpired()
+        .accounts({ ...expiringAccounts, cranker: taker.publicKey })
+        .signers([taker])
+        .rpc(),
+      "OfferNotExpired"
+    );
+  });
+
+  it("Take after expiry", async () => {
+    while ((await now()) < expiresAt) {
+      await sleep(1000);
+    }
+
+    await expectError(
+      program.methods
+        .take(new BN(1e6), [])
+        .accounts({ ...expiringAccounts })
+        .signers([taker])
+        .rpc(),
+      "OfferExpired"
+    );
+  });
+
+  it("Crank after expiry", async () => {
+    await program.methods
+      .crankExpired()
+      .accounts({ ...expiringAccounts, cranker: taker.publicKey })
+      .signers([taker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    expect(await connection.getAccountInfo(expiringAccounts.escrow)).to.be.null;
+    expect(await connection.getAccountInfo(expiringAccounts.vault)).to.be.null;
+  });
+
+  it("Make already expired", async () => {
+    const expiredSeed = new BN(randomBytes(8));
+    await expectError(
+      program.methods
+        .make(expiredSeed, new BN(1e6), new BN(1e6), new BN((await now()) - 60), null, null)
+        .accounts({ ...escrowAccounts(expiredSeed) })
+        .signers([maker])
+        .rpc(),
+      "InvalidExpiry"
+    );
+  });
+
+  const privateSeed = new BN(randomBytes(8));
+
+  it("Make private", async () => {
+    await program.methods
+      .make(privateSeed, new BN(1e6), new BN(1e6), null, taker.publicKey, null)
+      .accounts({ ...escrowAccounts(privateSeed) })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  it("Take private as someone else", async () => {
+    await expectError(
+      program.methods
+        .take(new BN(1e6), [])
+        .accounts({ ...escrowAccounts(privateSeed), ...outsiderAccounts })
+        .signers([outsider])
+        .rpc(),
+      "TakerNotAllowed"
+    );
+  });
+
+  it("Take private", async () => {
+    await program.methods
+      .take(new BN(1e6), [])
+      .accounts({ ...escrowAccounts(privateSeed) })
+      .signers([taker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  // leaves are the hash of a taker's key, pairs are hashed in sorted order like Escrow::can_take
+  const sha256 = (...data: Buffer[]) => createHash("sha256").update(Buffer.concat(data)).digest();
+  const hashPair = (a: Buffer, b: Buffer) =>
+    Buffer.compare(a, b) <= 0 ? sha256(a, b) : sha256(b, a);
+
+  // three allowed takers: root = hash(hash(taker, other), last)
+  const [takerLeaf, otherLeaf, lastLeaf] = [taker, Keypair.generate(), Keypair.generate()].map(
+    (k) => sha256(k.publicKey.toBuffer())
+  );
+  const root = hashPair(hashPair(takerLeaf, otherLeaf), lastLeaf);
+  const takerProof = [otherLeaf, lastLeaf].map((node) => [...node]);
+
+  const allowListSeed = new BN(randomBytes(8));
+
+  it("Make allow-listed", async () => {
+    await program.methods
+      .make(allowListSeed, new BN(1e6), new BN(1e6), null, null, [...root])
+      .accounts({ ...escrowAccounts(allowListSeed) })
+      .signers([maker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  it("Take allow-listed as someone else", async () => {
+    await expectError(
+      program.methods
+        .take(new BN(1e6), takerProof)
+        .accounts({ ...escrowAccounts(allowListSeed), ...outsiderAccounts })
+        .signers([outsider])
+        .rpc(),
+      "TakerNotAllowed"
+    );
+  });
+
+  it("Take allow-listed with a wrong proof", async () => {
+    await expectError(
+      program.methods
+        .take(new BN(1e6), [[...lastLeaf], [...otherLeaf]])
+        .accounts({ ...escrowAccounts(allowListSeed) })
+        .signers([taker])
+        .rpc(),
+      "TakerNotAllowed"
+    );
+  });
+
+  it("Take allow-listed", async () => {
+    await program.methods
+      .take(new BN(1e6), takerProof)
+      .accounts({ ...escrowAccounts(allowListSeed) })
+      .signers([taker])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  it("Make private and allow-listed", async () => {
+    const seed = new BN(randomBytes(8));
+    await expectError(
+      program.methods
+        .make(seed, new BN(1e6), new BN(1e6), null, taker.publicKey, [...root])
+        .accounts({ ...escrowAccounts(seed) })
+        .signers([maker])
+        .rpc(),
+      "InvalidTakerRestriction"
+    );
+  });
 });